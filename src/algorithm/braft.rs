@@ -1,14 +1,41 @@
-use core::mem;
+use core::{future::Future, mem, pin::Pin, time::Duration};
 
-use futures_lite::future::FutureExt;
+use futures_lite::future::{self, FutureExt};
+use num_traits::One;
 
-use alloc::{boxed::Box, vec::Vec};
+use alloc::{boxed::Box, format, vec::Vec};
 
 use crate::{
-    packet::{BroadcastCommit, BroadcastPropose, Packet},
-    App, Consensus, Error, Network, Result, Role, VoteSign, Voter,
+    event::{ConsensusEvent, Event, EventKind, EventListener},
+    handle::{Command, CommandQueue, ControlHandle, ShutdownHandle},
+    inbound::{Context, Inbound},
+    packet::{
+        BroadcastCommit, BroadcastPropose, NewRound, Packet, ResponsePropose, RoundChange, Voted,
+    },
+    quorum::QuorumCollector,
+    status::{ConsensusStatus, Status},
+    timeout::TimeoutPolicy,
+    App, Consensus, Error, Network, Result, Role, VoteSign, VoterConfig, VoterSet,
 };
 
+/// Borrow state of engine to check inbound packet.
+macro_rules! inbound_context {
+    ($braft:expr) => {
+        Context {
+            latest_epoch_id: &$braft.latest_epoch_id,
+            epoch_id: &$braft.epoch_id,
+            role: $braft.role,
+            proposer: &$braft.proposer,
+            round: $braft.round,
+            step: $braft.step,
+            fault_tolerance: $braft.quorum.fault_tolerance(),
+            voted: &$braft.voted,
+            depth: if $braft.pipelined { 2 } else { 1 },
+            voters: &$braft.voter_config,
+        }
+    };
+}
+
 /// Raft for blockchain.
 ///
 /// Variant of raft for blockchain.
@@ -24,18 +51,76 @@ where
     node_id: C::NodeId,
 
     role: Role,
+    proposer: C::NodeId,
+    /// Proposer on round 0 of current epoch, proposer of certified round rotates from it.
+    leader: Option<C::NodeId>,
 
     latest_epoch_id: C::EpochId,
+    latest_epoch_hash: C::EpochHash,
     epoch_id: C::EpochId,
     epoch_hash: C::EpochHash,
 
     round: u64,
     step: u8,
+    /// Epochs voted as follower, until committed.
+    voted: Vec<C::EpochId>,
+    /// Proposals voted and not committed, one of each epoch, kept across rounds.
+    locked: Vec<ConsensusVoted<C>>,
 
     weight: C::Weight,
-    total_weight: C::Weight,
+    quorum: QuorumCollector<C::EpochId, C::EpochHash>,
     vote_signs: Vec<VoteSign<C::Signature>>,
-    voter_set: Vec<Voter<C::NodeId, C::PublicKey, C::Weight>>,
+    voter_config: VoterConfig<C::NodeId, C::PublicKey, C::Weight>,
+
+    /// Round change votes after latest epoch.
+    round_votes: QuorumCollector<C::EpochId, C::EpochHash>,
+    /// Round change signatures after latest epoch, with round and proposal signer is locked on.
+    round_signs: Vec<RoundSign<C>>,
+    /// Highest round certified after latest epoch.
+    certified_round: u64,
+
+    last_commit_at: Option<Duration>,
+    listeners: Vec<EventListener<C>>,
+    shutdown: ShutdownHandle,
+    commands: CommandQueue<C>,
+    paused: bool,
+    /// Sequence of next heartbeat.
+    heartbeat_seq: u64,
+    /// Voter set is loaded from consensus again on next commit.
+    reload_voters: bool,
+
+    timeout_policy: TimeoutPolicy,
+    rng: u64,
+
+    pipelined: bool,
+    stable_leader: bool,
+
+    inbound: Inbound<C>,
+    /// Receive in flight, kept across ticks so no packet is lost when timer fires first.
+    recver: Option<Pin<Box<N::RecvFuture>>>,
+}
+
+type ConsensusPacket<C> =
+    Packet<<C as Consensus>::EpochId, <C as Consensus>::EpochHash, <C as Consensus>::Signature>;
+
+type ConsensusVoted<C> = Voted<<C as Consensus>::EpochId, <C as Consensus>::EpochHash>;
+
+/// Round change signature with round and proposal signer is locked on.
+type RoundSign<C> = (
+    u64,
+    VoteSign<<C as Consensus>::Signature>,
+    Option<ConsensusVoted<C>>,
+);
+
+/// Result of waiting packet.
+enum Received<C: Consensus> {
+    Packet(ConsensusPacket<C>, C::NodeId),
+    /// Timer fired first.
+    Timeout,
+    /// Moved to a round certified by a quorum, current step is abandoned.
+    NewRound,
+    /// Heartbeat of stable leader.
+    Heartbeat,
 }
 
 impl<N, A, C> BRaft<N, A, C>
@@ -47,55 +132,607 @@ where
     /// Build braft node
     ///
     /// Pass lowlevel network, consensus and application.
-    pub async fn new(network: N, consensus: C, app: A) -> Self {
+    ///
+    /// Return error if latest voter set is invalid.
+    pub async fn new(network: N, consensus: C, app: A) -> Result<Self> {
         let node_id = network.node_id();
 
         let (epoch_id, epoch_hash) = consensus.latest_epoch().await;
 
-        let voter_set = consensus.latest_voter_set().await;
+        let voter_config = VoterConfig::Single(VoterSet::new(consensus.latest_voter_set().await)?);
 
-        let total_weight = voter_set.iter().map(|e| e.weight.clone()).sum();
+        let quorum = QuorumCollector::new(consensus.fault_tolerance());
+        let round_votes = QuorumCollector::new(consensus.fault_tolerance());
 
-        let mut role = Role::Observer;
+        let timeout_policy = consensus.timeout_policy();
 
-        // TODO: need check proposer in voter_set.
-        let proposer = consensus.compute_proposer(&epoch_hash).await;
+        let inbound = Inbound::new(consensus.inbound_policy());
 
-        for i in &voter_set {
-            if i.voter_id == node_id {
-                role = Role::Follower;
-                break;
-            }
-        }
+        let pipelined = consensus.pipelined();
+        let stable_leader = consensus.stable_leader();
 
-        if node_id == proposer {
-            role = Role::Proposer;
-        }
+        // Seed of jitter, different on each node.
+        let rng = format!("{:?}", node_id)
+            .bytes()
+            .fold(0xcbf2_9ce4_8422_2325u64, |h, b| {
+                (h ^ b as u64).wrapping_mul(0x0000_0100_0000_01b3)
+            })
+            | 1;
 
         log::info!("Start node at epoch_id: {:?}", epoch_id);
 
-        Self {
+        let mut braft = Self {
             network,
             consensus,
             latest_epoch_id: epoch_id.clone(),
+            latest_epoch_hash: epoch_hash.clone(),
+            proposer: node_id.clone(),
+            leader: None,
             node_id,
             epoch_id,
             epoch_hash,
-            role,
+            role: Role::Observer,
             app,
-            voter_set,
+            voter_config,
             weight: num_traits::zero(),
-            total_weight,
+            quorum,
             vote_signs: Vec::new(),
+            round_votes,
+            round_signs: Vec::new(),
+            certified_round: 0,
             round: 0,
             step: 0,
+            voted: Vec::new(),
+            locked: Vec::new(),
+            last_commit_at: None,
+            listeners: Vec::new(),
+            shutdown: ShutdownHandle::default(),
+            commands: Default::default(),
+            paused: false,
+            heartbeat_seq: 0,
+            reload_voters: false,
+            timeout_policy,
+            rng,
+            pipelined,
+            stable_leader,
+            inbound,
+            recver: None,
+        };
+
+        braft.network.update_voters(&braft.voter_config);
+        braft.update_role().await;
+
+        Ok(braft)
+    }
+
+    /// Snapshot of node status.
+    pub fn status(&self) -> ConsensusStatus<C> {
+        let vote_signers = self
+            .vote_signs
+            .iter()
+            .filter_map(|s| self.voter_config.get(s.idx))
+            .map(|v| v.voter_id.clone())
+            .collect();
+
+        Status {
+            node_id: self.node_id.clone(),
+            role: self.role,
+            proposer: self.proposer.clone(),
+            latest_epoch_id: self.latest_epoch_id.clone(),
+            epoch_id: self.epoch_id.clone(),
+            epoch_hash: self.epoch_hash.clone(),
+            round: self.round,
+            step: self.step,
+            weight: self.weight.clone(),
+            voters: self.voter_config.clone(),
+            vote_signers,
+            last_commit_at: self.last_commit_at,
+        }
+    }
+
+    /// Subscribe event of this node.
+    ///
+    /// Listener is called on each role change, step/round transition, proposal, vote,
+    /// commit and error.
+    pub fn subscribe(&mut self, listener: impl FnMut(&ConsensusEvent<C>) + Send + 'static) {
+        self.listeners.push(Box::new(listener));
+    }
+
+    fn emit(&mut self, kind: EventKind<C::NodeId, C::EpochId, C::EpochHash, C::Weight>) {
+        if self.listeners.is_empty() {
+            return;
+        }
+
+        let event = Event {
+            epoch_id: self.epoch_id.clone(),
+            round: self.round,
+            step: self.step,
+            kind,
+        };
+
+        for listener in &mut self.listeners {
+            listener(&event);
+        }
+    }
+
+    fn set_step(&mut self, step: u8) {
+        if self.step != step {
+            self.emit(EventKind::StepChanged {
+                from: self.step,
+                to: step,
+            });
+            self.step = step;
+        }
+    }
+
+    fn set_round(&mut self, round: u64) {
+        self.inbound.next_round();
+
+        if self.round != round {
+            self.emit(EventKind::RoundChanged {
+                from: self.round,
+                to: round,
+            });
+            self.round = round;
+        }
+    }
+
+    fn received(
+        &mut self,
+        pkt: &Packet<C::EpochId, C::EpochHash, C::Signature>,
+        sender: &C::NodeId,
+    ) {
+        self.emit(EventKind::PacketReceived {
+            sender: sender.clone(),
+            packet: pkt.name(),
+        });
+    }
+
+    /// Check packet, queue it if valid.
+    fn admit(&mut self, pkt: ConsensusPacket<C>, sender: C::NodeId) {
+        self.received(&pkt, &sender);
+
+        if let Err(reason) = self
+            .inbound
+            .push(pkt, sender.clone(), &inbound_context!(self))
+        {
+            log::debug!("Drop packet from {:?}: {}", sender, reason);
+            self.emit(EventKind::PacketDropped { sender, reason });
+        }
+    }
+
+    /// Receive next packet, `None` if timer fired first.
+    ///
+    /// Receive isn't cancelled by timer, next call continues it.
+    async fn recv_one<T>(
+        &mut self,
+        timer: &mut Pin<Box<T>>,
+    ) -> Result<Option<(ConsensusPacket<C>, C::NodeId)>>
+    where
+        T: Future<Output = ()>,
+    {
+        let network = &self.network;
+        let recver = self.recver.get_or_insert_with(|| Box::pin(network.recv()));
+
+        // Only waiting is abandoned on shutdown, receive in flight is kept.
+        let stop = async {
+            self.shutdown.wait().await;
+            Err(Error::Shutdown)
+        };
+        let recver = async {
+            recver
+                .as_mut()
+                .await
+                .map(Some)
+                .map_err(Error::network_error)
+        };
+        let timeout = async {
+            timer.as_mut().await;
+            Ok(None)
+        };
+
+        match stop.or(recver.or(timeout)).await {
+            res @ (Ok(None) | Err(Error::Shutdown)) => res,
+            res => {
+                self.recver = None;
+                res
+            }
+        }
+    }
+
+    /// Queue packets already arrived.
+    async fn drain(&mut self) -> Result<()> {
+        for _ in 0..self.inbound.policy().queue_size() {
+            let network = &self.network;
+            let recver = self.recver.get_or_insert_with(|| Box::pin(network.recv()));
+
+            match future::poll_once(recver.as_mut()).await {
+                Some(r) => {
+                    self.recver = None;
+                    let (pkt, sender) = r.map_err(Error::network_error)?;
+                    self.admit(pkt, sender);
+                }
+                None => break,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Queue packets until timer fired.
+    async fn pump<T>(&mut self, timer: &mut Pin<Box<T>>) -> Result<()>
+    where
+        T: Future<Output = ()>,
+    {
+        while let Some((pkt, sender)) = self.recv_one(timer).await? {
+            self.admit(pkt, sender);
+        }
+
+        Ok(())
+    }
+
+    /// Receive next valid packet.
+    ///
+    /// Packets already arrived are queued, so packets of flooding sender are processed after
+    /// others. Round packets are handled here, on any step.
+    async fn recv_packet<T>(&mut self, timer: &mut Pin<Box<T>>) -> Result<Received<C>>
+    where
+        T: Future<Output = ()>,
+    {
+        loop {
+            let popped = self.inbound.pop(&inbound_context!(self));
+
+            // Vote signatures are only verified on packet passed inbound checks.
+            if let Some((pkt, sender)) = &popped {
+                if !self.network.verify_votes(pkt) {
+                    log::warn!("Bad vote signature in packet from {:?}", sender);
+                    self.inbound.penalize(sender);
+                    self.emit(EventKind::PacketDropped {
+                        sender: sender.clone(),
+                        reason: "bad_signature",
+                    });
+                    continue;
+                }
+            }
+
+            match popped {
+                Some((pkt @ (Packet::RoundChange(_) | Packet::NewRound(_)), _)) => {
+                    if self.sync_round(pkt) {
+                        return Ok(Received::NewRound);
+                    }
+                    continue;
+                }
+                Some((Packet::Heartbeat(_), sender)) => {
+                    if self.stable_leader && self.role.is_follower() && sender == self.proposer {
+                        return Ok(Received::Heartbeat);
+                    }
+                    continue;
+                }
+                Some((pkt, sender)) => return Ok(Received::Packet(pkt, sender)),
+                None => {}
+            }
+
+            match self.recv_one(timer).await? {
+                Some((pkt, sender)) => {
+                    self.admit(pkt, sender);
+                    self.drain().await?;
+                }
+                None => return Ok(Received::Timeout),
+            }
+        }
+    }
+
+    /// Count round change vote, or follow new round.
+    ///
+    /// Round change votes of a quorum certify the round, certificate is broadcast as
+    /// `NewRound`. Return true if moved to a higher round.
+    fn sync_round(&mut self, pkt: ConsensusPacket<C>) -> bool {
+        let (epoch_id, epoch_hash, round, voted, vote_signs) = match pkt {
+            Packet::RoundChange(rc) => {
+                // Checked by inbound.
+                let sign = match rc.vote_sign {
+                    Some(sign) => sign,
+                    None => return false,
+                };
+
+                if rc.round <= self.certified_round
+                    || rc.epoch_hash != self.latest_epoch_hash
+                    || !self
+                        .round_votes
+                        .add_vote(&rc.epoch_id, rc.round, &rc.epoch_hash, sign.idx)
+                {
+                    return false;
+                }
+
+                // Only lock on next epoch is carried.
+                let voted = rc.voted.filter(|v| v.epoch_id == self.next_epoch_id());
+                self.round_signs.push((rc.round, sign, voted));
+
+                if !self.round_votes.has_quorum(
+                    &rc.epoch_id,
+                    rc.round,
+                    &rc.epoch_hash,
+                    &self.voter_config,
+                ) {
+                    return false;
+                }
+
+                let signs = self.round_signs.iter().filter(|(r, _, _)| *r == rc.round);
+
+                let vote_signs = signs.clone().map(|(_, s, _)| s.clone()).collect();
+                let voted = signs
+                    .filter_map(|(_, _, v)| v.as_ref())
+                    .max_by_key(|v| v.round)
+                    .cloned();
+
+                (
+                    rc.epoch_id,
+                    rc.epoch_hash,
+                    rc.round,
+                    voted,
+                    Some(vote_signs),
+                )
+            }
+            Packet::NewRound(nr) => (nr.epoch_id, nr.epoch_hash, nr.round, nr.voted, None),
+            _ => return false,
+        };
+
+        if round <= self.certified_round {
+            return false;
+        }
+
+        if epoch_hash != self.latest_epoch_hash {
+            log::warn!(
+                "Round certificate on epoch {:?} with other hash: {:?}, ignore it",
+                epoch_id,
+                epoch_hash
+            );
+            return false;
+        }
+
+        self.certified_round = round;
+        self.round_signs.retain(|(r, _, _)| *r > round);
+
+        if let Some(vote_signs) = vote_signs {
+            // Help nodes behind to catch up.
+            self.network.send_unsigned(
+                None,
+                Packet::NewRound(NewRound {
+                    epoch_id,
+                    epoch_hash,
+                    round,
+                    voted: voted.clone(),
+                    vote_signs,
+                }),
+            );
+        }
+
+        let elected = self.elect(round);
+
+        // Certificate of own round is only followed before anything is proposed on it.
+        if !elected && (round < self.round || (round == self.round && self.step > 0)) {
+            return false;
+        }
+
+        log::info!("Move to round {} certified by quorum", round);
+
+        self.emit(EventKind::RoundCertified { round });
+        self.drop_proposal();
+        self.voted.clear();
+
+        // Highest lock of a quorum replaces own lock on next epoch. Own lock missing from
+        // certificate never reached a quorum, so it's released.
+        let next = self.next_epoch_id();
+        self.locked.retain(|l| l.epoch_id != next);
+        self.locked.extend(voted.filter(|v| v.epoch_id == next));
+
+        self.set_round(round.max(self.round));
+        self.set_step(0);
+
+        true
+    }
+
+    /// Elect proposer of certified round, rotating from leader of current epoch.
+    ///
+    /// Return true if proposer changed.
+    fn elect(&mut self, round: u64) -> bool {
+        let voters = self.voter_config.voters();
+
+        let base = self
+            .leader
+            .as_ref()
+            .and_then(|leader| voters.iter().position(|v| &v.voter_id == leader))
+            .unwrap_or(0);
+        let idx = (base as u64 + round % voters.len() as u64) % voters.len() as u64;
+        let proposer = voters[idx as usize].voter_id.clone();
+
+        if proposer == self.proposer {
+            return false;
+        }
+
+        log::info!("Elect proposer {:?} on round {}", proposer, round);
+
+        self.set_proposer(proposer);
+
+        true
+    }
+
+    fn timeout(&mut self, step: u8) {
+        self.emit(EventKind::Timeout {
+            role: self.role,
+            step,
+        });
+    }
+
+    /// Handle to stop `run`.
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        self.shutdown.clone()
+    }
+
+    /// Handle to send command to this node.
+    pub fn control_handle(&self) -> ControlHandle<C> {
+        ControlHandle::new(self.commands.clone(), self.shutdown.clone())
+    }
+
+    async fn process_commands(&mut self) {
+        loop {
+            // Lock is released before process command.
+            let command = self.commands.lock().pop_front();
+
+            match command {
+                Some(command) => self.process_command(command).await,
+                None => break,
+            }
         }
     }
 
+    async fn process_command(&mut self, command: Command<C>) {
+        log::info!("Process command: {:?}", command);
+
+        match command {
+            Command::ForceRoundChange => self.abandon_round(),
+            Command::PauseProposing(paused) => self.paused = paused,
+            Command::StepDown => {
+                // Start a round change, proposer of next round takes over once it's certified.
+                if self.role.is_proposer() {
+                    self.abandon_round();
+                }
+            }
+            // Only applied on commit, voter set never changes inside an epoch.
+            Command::ReloadVoterSet => self.reload_voters = true,
+            Command::DumpStatus(sender) => {
+                let _ = sender.send(self.status());
+            }
+        }
+    }
+
+    /// Build timer of step on current round.
+    fn step_timer(&mut self, role: Role, step: u8) -> C::Timer {
+        // xorshift64
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+
+        let timeout = self
+            .timeout_policy
+            .timeout(&role, step, self.round, self.rng);
+
+        log::debug!(
+            "Timer of {:?}/{} on round {}: {:?}",
+            role,
+            step,
+            self.round,
+            timeout
+        );
+
+        self.consensus.timer(timeout)
+    }
+
+    /// Drop proposal of this node and collected votes.
+    fn drop_proposal(&mut self) {
+        self.epoch_id = self.latest_epoch_id.clone();
+        self.epoch_hash = self.latest_epoch_hash.clone();
+        self.vote_signs.clear();
+        self.weight = num_traits::zero();
+    }
+
+    /// Drop proposal and votes, move to next round.
+    ///
+    /// Voter broadcasts `RoundChange`, so other voters can follow once a quorum moved.
+    fn abandon_round(&mut self) {
+        self.drop_proposal();
+        self.voted.clear();
+        self.set_round(self.round + 1);
+        self.set_step(0);
+
+        if !self.role.is_observer() {
+            let next = self.next_epoch_id();
+            let voted = self.locked.iter().find(|l| l.epoch_id == next).cloned();
+
+            self.network.send_unsigned(
+                None,
+                Packet::RoundChange(RoundChange {
+                    epoch_id: self.latest_epoch_id.clone(),
+                    epoch_hash: self.latest_epoch_hash.clone(),
+                    round: self.round,
+                    voted,
+                    vote_sign: None,
+                }),
+            );
+        }
+    }
+
+    /// Epoch after latest committed epoch, round change is on it.
+    fn next_epoch_id(&self) -> C::EpochId {
+        self.latest_epoch_id.clone() + One::one()
+    }
+
+    /// Lock on proposal voted on round, replace lock of same epoch.
+    fn lock(&mut self, epoch_id: C::EpochId, epoch_hash: C::EpochHash, round: u64) {
+        self.locked.retain(|l| l.epoch_id != epoch_id);
+        self.locked.push(Voted {
+            epoch_id,
+            epoch_hash,
+            round,
+        });
+    }
+
+    /// Drive consensus until shutdown requested.
+    ///
+    /// Only waiting packet or timer is abandoned on shutdown, calls of app and commit of epoch
+    /// always run to completion.
+    /// Yield to other tasks between ticks.
+    /// Errors of bad packet are logged and ignored, other errors stop the engine.
+    /// `App::shutdown` is called before return, error of tick is returned before its error.
+    pub async fn run(&mut self) -> Result<()> {
+        let mut res = Ok(());
+
+        while !self.shutdown.is_shutdown() {
+            match self.do_tick().await {
+                Ok(()) => {}
+                Err(Error::Shutdown) => break,
+                Err(e @ (Error::UnexpectedPacket | Error::NoSignature)) => {
+                    log::warn!("Bad packet: {:?}, ignore it", e);
+                }
+                Err(e) => {
+                    res = Err(e);
+                    break;
+                }
+            }
+
+            // Tick may finish without waiting, let other tasks run.
+            future::yield_now().await;
+        }
+
+        log::info!("Shutdown node at epoch_id: {:?}", self.latest_epoch_id);
+
+        let shutdown_res = self.app.shutdown().await.map_err(Error::app_error);
+
+        if let (Err(_), Err(e)) = (&res, &shutdown_res) {
+            log::warn!("Shutdown app failed: {:?}", e);
+        }
+
+        res.and(shutdown_res)
+    }
+
     /// Trigger consensus.
     ///
     /// Run this method on loop.
     pub async fn do_tick(&mut self) -> Result<()> {
+        let res = self.tick().await;
+
+        match &res {
+            Ok(()) | Err(Error::Shutdown) => {}
+            Err(e) => self.emit(EventKind::Error(format!("{:?}", e))),
+        }
+
+        res
+    }
+
+    async fn tick(&mut self) -> Result<()> {
+        self.process_commands().await;
+        self.drain().await?;
+
         log::info!(
             "On epoch_id/round/step: {:?}/{}/{}",
             self.latest_epoch_id,
@@ -110,26 +747,57 @@ where
         } else if self.role.is_follower() && self.step == 1 {
             // Wait BroadcastCommit.
 
-            let timer = async {
-                self.consensus.step_timer(&Role::Follower, 1);
-                Err(Error::Timeout)
-            };
+            let mut timer = Box::pin(self.step_timer(Role::Follower, 1));
 
-            let recver = async {
-                let pkt = self.network.recv().await.map_err(Error::network_error)?;
-                Ok(pkt)
-            };
+            match self.recv_packet(&mut timer).await? {
+                Received::Packet(p, _) => self.wait_commit(p).await?,
+                Received::Timeout => {
+                    self.timeout(1);
+                    self.abandon_round();
+                }
+                Received::NewRound => {}
+                // Leader is idle, voted proposal is abandoned.
+                Received::Heartbeat => {
+                    self.voted.clear();
+                    self.set_step(0);
+                }
+            }
+        } else if self.role.is_proposer() && self.step == 0 && self.round > self.certified_round {
+            // Don't propose on round before it's certified, proposer may change on it.
 
-            let pkt = recver.or(timer).await;
+            let mut timer = Box::pin(self.step_timer(Role::Proposer, 0));
 
-            match pkt {
-                Ok((p, _sender)) => self.wait_commit(p).await?,
-                Err(Error::Timeout) => {
-                    self.round += 1;
-                    self.step = 0;
+            match self.recv_packet(&mut timer).await? {
+                Received::Timeout => {
+                    self.timeout(0);
+                    self.abandon_round();
+                }
+                Received::Packet(pkt, _) => {
+                    log::debug!("Drop packet on uncertified round: {:?}", pkt);
                 }
-                Err(e) => return Err(e),
+                Received::NewRound | Received::Heartbeat => {}
             }
+        } else if self.role.is_proposer() && self.step == 0 && self.paused && self.stable_leader {
+            // Proposing paused, keep leading with heartbeat.
+
+            self.heartbeat_seq += 1;
+            self.network.send_unsigned(
+                None,
+                Packet::heartbeat_from_id_hash(
+                    self.latest_epoch_id.clone(),
+                    self.latest_epoch_hash.clone(),
+                    self.round,
+                    self.heartbeat_seq,
+                ),
+            );
+
+            let mut timer = Box::pin(self.consensus.timer(self.timeout_policy.heartbeat()));
+            self.pump(&mut timer).await?;
+        } else if self.role.is_proposer() && self.step == 0 && self.paused {
+            // Proposing paused, only queue packets.
+
+            let mut timer = Box::pin(self.step_timer(Role::Proposer, 0));
+            self.pump(&mut timer).await?;
         } else if self.role.is_proposer() && self.step == 0 {
             // Propose epoch.
 
@@ -138,47 +806,175 @@ where
             // Collect all `ResponsePropose`.
 
             self.collect_propose().await?;
-        }
+        } else if self.role.is_observer() {
+            // Only sync `BroadcastCommit`.
 
-        // Update role and weight
-        if self.step == 0 && self.round == 0 {
-            let proposer = self.consensus.compute_proposer(&self.epoch_hash).await;
+            self.wait_observe().await?;
+        }
 
-            log::debug!("proposer: {:?}, node_id: {:?}", proposer, self.node_id);
+        Ok(())
+    }
 
-            if proposer == self.node_id {
-                self.role = Role::Proposer;
+    /// Compute role for current epoch.
+    ///
+    /// Stable leader keeps leading while it's in voter set, or proposer is computed from latest
+    /// epoch hash. Node not in voter set is observer.
+    async fn update_role(&mut self) {
+        let proposer = match &self.leader {
+            Some(_) if self.stable_leader && self.voter_config.contains(&self.proposer) => {
+                self.proposer.clone()
             }
+            _ => {
+                self.consensus
+                    .compute_proposer(&self.latest_epoch_hash)
+                    .await
+            }
+        };
 
-            // self.role = self.consensus.compute_role(&self.epoch_hash);
-            self.weight = num_traits::zero();
+        self.leader = Some(proposer.clone());
+
+        self.set_proposer(proposer);
+    }
+
+    /// Set proposer, then compute role of this node.
+    fn set_proposer(&mut self, proposer: C::NodeId) {
+        log::debug!("proposer: {:?}, node_id: {:?}", proposer, self.node_id);
+
+        if !self.voter_config.contains(&proposer) {
+            log::warn!("Proposer {:?} is not in voter set", proposer);
         }
 
-        Ok(())
+        let role = if !self.voter_config.contains(&self.node_id) {
+            Role::Observer
+        } else if proposer == self.node_id {
+            Role::Proposer
+        } else {
+            Role::Follower
+        };
+
+        self.proposer = proposer;
+
+        if self.role != role {
+            self.emit(EventKind::RoleChanged {
+                from: self.role,
+                to: role,
+            });
+            self.role = role;
+        }
     }
 
-    // ---------------------------- wait_broadcast_propose
-    async fn wait_broadcast_propose(&mut self) -> Result<()> {
-        let timer = async {
-            self.consensus.step_timer(&Role::Follower, 0).await;
-            Err(Error::Timeout)
+    /// Commit epoch to app, then apply voter set for next epoch.
+    ///
+    /// Invalid voter set from app is ignored, the latest voter set is used.
+    /// On joint consensus, a changed voter set starts a transition epoch.
+    ///
+    /// Return true if voter config changed.
+    async fn commit_epoch(
+        &mut self,
+        epoch_id: C::EpochId,
+        epoch_hash: C::EpochHash,
+    ) -> Result<bool> {
+        let voters = self
+            .app
+            .commit(&epoch_id, &epoch_hash)
+            .await
+            .map_err(Error::app_error)?;
+
+        self.latest_epoch_id = epoch_id.clone();
+        self.latest_epoch_hash = epoch_hash.clone();
+        self.last_commit_at = self.consensus.now();
+
+        self.emit(EventKind::Committed {
+            epoch_id: epoch_id.clone(),
+            epoch_hash,
+        });
+
+        let voters = if mem::take(&mut self.reload_voters) {
+            log::info!("Reload voter set on epoch {:?}", epoch_id);
+            self.consensus.latest_voter_set().await
+        } else {
+            voters
         };
 
-        let recver = async {
-            let pkt = self.network.recv().await.map_err(Error::network_error)?;
-            Ok(pkt)
+        let voter_set = match VoterSet::new(voters) {
+            Ok(voter_set) => voter_set,
+            Err(e) => {
+                log::warn!(
+                    "Invalid voter set on epoch {:?}: {:?}, fallback to latest voter set",
+                    epoch_id,
+                    e
+                );
+                self.voter_config.latest().clone()
+            }
         };
 
-        let pkt = recver.or(timer).await;
-        log::debug!("pkt: {:?}", pkt);
+        let changed = !self.voter_config.latest().same_voters(&voter_set);
+        let was_joint = self.voter_config.is_joint();
 
-        match pkt {
-            Ok((p, sender)) => self.wait_propose(p, sender).await?,
-            Err(Error::Timeout) => {
-                self.round += 1;
-                self.step = 0;
+        self.voter_config = self
+            .voter_config
+            .next(voter_set, self.consensus.joint_consensus());
+
+        if self.voter_config.is_joint() {
+            log::info!("Voter set changed, next epoch is a transition epoch");
+        }
+
+        if changed || was_joint {
+            self.network.update_voters(&self.voter_config);
+        }
+
+        if changed {
+            self.emit(EventKind::VoterSetChanged {
+                joint: self.voter_config.is_joint(),
+            });
+        }
+
+        self.voted.retain(|e| e > &epoch_id);
+        self.locked.retain(|l| l.epoch_id > epoch_id);
+        self.quorum.prune(&epoch_id);
+        self.round_votes.clear();
+        self.round_signs.clear();
+        self.certified_round = 0;
+        self.inbound.committed();
+        self.update_role().await;
+
+        if !self.role.is_follower() {
+            self.voted.clear();
+        }
+
+        Ok(changed || was_joint)
+    }
+
+    // ---------------------------- wait_observe
+    async fn wait_observe(&mut self) -> Result<()> {
+        let mut timer = Box::pin(self.step_timer(Role::Observer, 0));
+
+        match self.recv_packet(&mut timer).await? {
+            Received::Packet(p, _) => self.wait_commit(p).await?,
+            Received::Timeout => self.timeout(0),
+            // Observer doesn't accept round packets and heartbeat.
+            Received::NewRound | Received::Heartbeat => {}
+        }
+
+        Ok(())
+    }
+    // ---------------------------- end wait_observe
+
+    // ---------------------------- wait_broadcast_propose
+    async fn wait_broadcast_propose(&mut self) -> Result<()> {
+        let mut timer = Box::pin(self.step_timer(Role::Follower, 0));
+
+        match self.recv_packet(&mut timer).await? {
+            Received::Packet(p, sender) => {
+                log::debug!("pkt: {:?}", p);
+                self.wait_propose(p, sender).await?
+            }
+            Received::Timeout => {
+                self.timeout(0);
+                self.abandon_round();
             }
-            Err(e) => return Err(e),
+            // Leader alive, wait next proposal.
+            Received::NewRound | Received::Heartbeat => {}
         }
 
         Ok(())
@@ -191,8 +987,8 @@ where
     ) -> Result<()> {
         match pkt {
             Packet::BroadcastPropose(bc) => self.process_propose(sender, bc).await?,
-            Packet::ResponsePropose(_) => self.error_packet(&pkt),
             Packet::BroadcastCommit(bc) => self.verify_and_accept_epoch(bc).await?,
+            _ => self.error_packet(&pkt),
         }
         Ok(())
     }
@@ -204,6 +1000,49 @@ where
     ) -> Result<()> {
         let epoch_id = pkt.epoch_id;
         let epoch_hash = pkt.epoch_hash;
+        let round = pkt.round;
+
+        self.emit(EventKind::ProposalReceived {
+            sender: sender.clone(),
+            epoch_id: epoch_id.clone(),
+            epoch_hash: epoch_hash.clone(),
+        });
+
+        // Proposal on pipeline is of proposer after last voted epoch.
+        let proposer = match self.voted.last() {
+            None => self.proposer.clone(),
+            Some(_) if self.stable_leader => self.proposer.clone(),
+            Some(last) => match self.locked.iter().find(|l| &l.epoch_id == last) {
+                Some(l) => self.consensus.compute_proposer(&l.epoch_hash).await,
+                None => self.proposer.clone(),
+            },
+        };
+
+        if sender != proposer || (self.voted.is_empty() && round != self.round) {
+            log::warn!(
+                "Proposal from {:?} on round {}, expect proposer {:?} on round {}. ignore this packet",
+                sender,
+                round,
+                proposer,
+                self.round
+            );
+            return Ok(());
+        }
+
+        if let Some(l) = self
+            .locked
+            .iter()
+            .find(|l| l.epoch_id == epoch_id && l.epoch_hash != epoch_hash)
+        {
+            log::warn!(
+                "Locked on epoch {:?} with hash {:?} of round {}, ignore proposal of hash {:?}",
+                epoch_id,
+                l.epoch_hash,
+                l.round,
+                epoch_hash
+            );
+            return Ok(());
+        }
 
         if self.epoch_id < epoch_id {
             self.app
@@ -213,8 +1052,13 @@ where
 
             self.network.send_unsigned(
                 Some(sender),
-                Packet::response_propose_from_id_hash(epoch_id, epoch_hash),
+                Packet::response_propose_from_id_hash(epoch_id.clone(), epoch_hash.clone(), round),
             );
+
+            // Wait commit of voted epoch.
+            self.lock(epoch_id.clone(), epoch_hash, round);
+            self.voted.push(epoch_id);
+            self.set_step(1);
         } else {
             log::warn!(
                 "Receive error epoch id on `BroadcastPropose`, expect: > {:?}, got: {:?}. ignore this packet",
@@ -238,15 +1082,11 @@ where
             self.epoch_id = epoch_id.clone();
             self.epoch_hash = epoch_hash.clone();
 
-            self.round = 0;
-            self.step = 0;
+            self.set_round(0);
+            self.commit_epoch(epoch_id, epoch_hash).await?;
 
-            let vs = self
-                .app
-                .commit(&epoch_id, &epoch_hash)
-                .await
-                .map_err(Error::app_error)?;
-            self.voter_set = vs;
+            // Commit of later voted epoch is in flight.
+            self.set_step(if self.voted.is_empty() { 0 } else { 1 });
         } else {
             log::warn!(
                 "Receive error epoch id on `BroadcastCommit`, expect: > {:?}, got: {:?}. ignore this packet",
@@ -285,19 +1125,46 @@ where
     async fn propose_epoch(&mut self) -> Result<()> {
         log::debug!("Enter propose epoch");
 
-        let (epoch_id, epoch_hash) = self.app.propose_epoch().await.map_err(Error::app_error)?;
+        // Epoch locked on may be committed by a quorum, propose it again.
+        let next = self.epoch_id.clone() + One::one();
+        let (epoch_id, epoch_hash) = match self.locked.iter().find(|l| l.epoch_id == next) {
+            Some(l) => {
+                log::info!("Propose locked epoch {:?} again", l.epoch_id);
+                (l.epoch_id.clone(), l.epoch_hash.clone())
+            }
+            None => self.app.propose_epoch().await.map_err(Error::app_error)?,
+        };
 
         log::debug!("propose epoch: {:?} => {:?}", epoch_id, epoch_hash);
 
         self.epoch_id = epoch_id.clone();
         self.epoch_hash = epoch_hash.clone();
+        self.weight = num_traits::zero();
+
+        self.emit(EventKind::Proposed {
+            epoch_id: epoch_id.clone(),
+            epoch_hash: epoch_hash.clone(),
+        });
+
+        let vote_sign = self.network.sign_vote(&epoch_id, &epoch_hash);
 
         self.network.send_unsigned(
             None,
-            Packet::broadcast_propose_from_id_hash(epoch_id, epoch_hash),
+            Packet::BroadcastPropose(BroadcastPropose {
+                epoch_id: epoch_id.clone(),
+                epoch_hash: epoch_hash.clone(),
+                round: self.round,
+                vote_sign: vote_sign.clone(),
+            }),
         );
 
-        self.step = 1;
+        self.lock(epoch_id.clone(), epoch_hash.clone(), self.round);
+        self.set_step(1);
+
+        // Count own vote, don't depend on broadcast looped back.
+        if vote_sign.is_some() {
+            self.add_weight(epoch_id, epoch_hash, vote_sign)?;
+        }
 
         Ok(())
     }
@@ -307,60 +1174,146 @@ where
     async fn collect_propose(&mut self) -> Result<()> {
         log::debug!("Enter collect propose");
 
-        let mut flag = true;
+        let mut grace = false;
 
-        let timer = self.consensus.step_timer(&Role::Proposer, 1);
+        let mut timer = Box::pin(self.step_timer(Role::Proposer, 1));
 
-        // Make this future to unpin.
-        let mut timer = Box::pin(async move {
-            timer.await;
-            Err(Error::Timeout)
-        });
+        loop {
+            if !grace && self.reached_quorum() {
+                let quorum_grace = self.timeout_policy.quorum_grace();
 
-        while flag {
-            let recver = self.network.recv();
+                if quorum_grace.is_zero() {
+                    break;
+                }
 
-            let recver = async move {
-                let pkt = recver.await.map_err(Error::network_error)?;
-                Ok(pkt)
-            };
+                // Collect more signatures for commit.
+                grace = true;
+                timer = Box::pin(self.consensus.timer(quorum_grace));
+            }
 
-            let pkt = recver.or(&mut timer).await;
-            log::debug!("receive packt: {:?}", pkt);
+            if grace && self.vote_signs.len() >= self.voter_config.voters().len() {
+                break;
+            }
 
-            match pkt {
-                Ok((pkt, _sender)) => self.collect_propose_packet(pkt)?,
-                Err(Error::Timeout) => flag = false,
-                Err(e) => return Err(e),
+            match self.recv_packet(&mut timer).await? {
+                Received::Packet(pkt, sender) => {
+                    log::debug!("receive packt: {:?}", pkt);
+                    self.collect_propose_packet(pkt, sender)?
+                }
+                Received::Timeout if grace => break,
+                Received::Timeout => {
+                    self.timeout(1);
+                    break;
+                }
+                // Proposal abandoned on new round.
+                Received::NewRound => return Ok(()),
+                // Only follower receives heartbeat.
+                Received::Heartbeat => {}
             }
         }
 
+        if self.pipelined && self.reached_quorum() {
+            return self.pipeline().await;
+        }
+
+        if !self.reached_quorum() {
+            // Never commit without quorum, propose again on next round.
+            log::info!(
+                "No quorum on epoch_id/round: {:?}/{}, abandon proposal",
+                self.epoch_id,
+                self.round
+            );
+            self.abandon_round();
+            return Ok(());
+        }
+
+        let vote_signs = mem::take(&mut self.vote_signs);
+
+        self.emit(EventKind::QuorumReached {
+            weight: self.weight.clone(),
+        });
+
+        self.network.send_unsigned(
+            None,
+            Packet::broadcast_commit_from_id_hash(
+                self.epoch_id.clone(),
+                self.epoch_hash.clone(),
+                vote_signs,
+            ),
+        );
+
+        self.weight = num_traits::zero();
+        self.set_step(0);
+        self.set_round(0);
+
+        self.commit_epoch(self.epoch_id.clone(), self.epoch_hash.clone())
+            .await?;
+
+        Ok(())
+    }
+
+    /// Vote of this node on proposal of this round counted.
+    fn own_vote_counted(&self) -> bool {
+        let idx = match self.voter_config.index_of(&self.node_id) {
+            Some(idx) => idx,
+            None => return false,
+        };
+
+        self.quorum
+            .votes(&self.epoch_id, self.round, &self.epoch_hash)
+            .is_some_and(|v| v.voters().contains(&idx))
+    }
+
+    /// Proposal of this round reached quorum.
+    fn reached_quorum(&self) -> bool {
+        self.quorum.has_quorum(
+            &self.epoch_id,
+            self.round,
+            &self.epoch_hash,
+            &self.voter_config,
+        )
+    }
+
+    /// Broadcast commit of epoch reached quorum, propose next epoch before commit it.
+    ///
+    /// Next epoch is only proposed if this node is its proposer. Proposal is abandoned if role
+    /// or voter config changed on commit.
+    async fn pipeline(&mut self) -> Result<()> {
+        let epoch_id = self.epoch_id.clone();
+        let epoch_hash = self.epoch_hash.clone();
         let vote_signs = mem::take(&mut self.vote_signs);
 
-        let one: C::Weight = num_traits::one();
-        let two: C::Weight = one.clone() + one;
-        if self.weight.clone() * two <= self.total_weight {
-            self.round += 1;
-            self.step = 0;
+        self.emit(EventKind::QuorumReached {
+            weight: self.weight.clone(),
+        });
+        self.weight = num_traits::zero();
+
+        self.network.send_unsigned(
+            None,
+            Packet::broadcast_commit_from_id_hash(epoch_id.clone(), epoch_hash.clone(), vote_signs),
+        );
+
+        self.set_round(0);
+
+        let proposer = if self.stable_leader {
+            self.proposer.clone()
         } else {
-            self.network.send_unsigned(
-                None,
-                Packet::broadcast_commit_from_id_hash(
-                    self.epoch_id.clone(),
-                    self.epoch_hash.clone(),
-                    vote_signs,
-                ),
-            );
+            self.consensus.compute_proposer(&epoch_hash).await
+        };
+
+        if proposer == self.node_id && !self.paused {
+            self.propose_epoch().await?;
+        } else {
+            self.set_step(0);
         }
 
-        self.step = 0;
-        self.round = 0;
-        self.latest_epoch_id = self.epoch_id.clone();
+        let changed = self.commit_epoch(epoch_id, epoch_hash).await?;
 
-        self.app
-            .commit(&self.epoch_id, &self.epoch_hash)
-            .await
-            .map_err(Error::app_error)?;
+        if self.step == 1 && (changed || !self.role.is_proposer()) {
+            log::info!("Abandon proposal of epoch {:?}", self.epoch_id);
+            self.drop_proposal();
+            self.set_step(0);
+        }
 
         Ok(())
     }
@@ -375,12 +1328,39 @@ where
             // Only process right vote. beacuse raft is not BFT.
             let sign = vote_sign.ok_or(Error::NoSignature)?;
 
-            if let Some(voter) = self.voter_set.get(sign.idx as usize) {
-                self.weight += voter.weight.clone();
-            } else {
-                log::error!("index of packet out of bound");
+            self.emit(EventKind::VoteReceived {
+                idx: sign.idx,
+                epoch_id: epoch_id.clone(),
+                epoch_hash: epoch_hash.clone(),
+            });
+
+            let weight = match self.voter_config.get(sign.idx) {
+                Some(voter) => voter.weight.clone(),
+                None => {
+                    log::error!("index of packet out of bound");
+                    return Ok(());
+                }
+            };
+
+            if !self
+                .quorum
+                .add_vote(&epoch_id, self.round, &epoch_hash, sign.idx)
+            {
+                log::warn!(
+                    "Duplicate vote from voter {} on epoch_id/round: {:?}/{}, ignore it",
+                    sign.idx,
+                    epoch_id,
+                    self.round
+                );
+                self.emit(EventKind::DuplicateVote { idx: sign.idx });
+                return Ok(());
             }
 
+            self.weight += weight;
+            self.emit(EventKind::VoteCounted {
+                idx: sign.idx,
+                weight: self.weight.clone(),
+            });
             self.vote_signs.push(sign);
         } else {
             log::error!(
@@ -397,20 +1377,29 @@ where
     fn collect_propose_packet(
         &mut self,
         pkt: Packet<C::EpochId, C::EpochHash, C::Signature>,
+        sender: C::NodeId,
     ) -> Result<()> {
         match pkt {
-            Packet::ResponsePropose(rp) => {
-                let epoch_id = rp.epoch_id;
-                let epoch_hash = rp.epoch_hash;
-                let vote_sign = rp.vote_sign;
-
+            // Own vote counted on propose.
+            Packet::BroadcastPropose(_) if sender == self.node_id && self.own_vote_counted() => {
+                log::debug!("Own proposal looped back, ignore it");
+            }
+            // Only votes on proposal of this round are counted.
+            Packet::ResponsePropose(ResponsePropose {
+                epoch_id,
+                epoch_hash,
+                round,
+                vote_sign,
+            }) if round == self.round => {
                 self.add_weight(epoch_id, epoch_hash, vote_sign)?;
             }
-            Packet::BroadcastPropose(rp) => {
-                let epoch_id = rp.epoch_id;
-                let epoch_hash = rp.epoch_hash;
-                let vote_sign = rp.vote_sign;
-
+            // Own proposal signed on send, only proposer of this round proposes.
+            Packet::BroadcastPropose(BroadcastPropose {
+                epoch_id,
+                epoch_hash,
+                round,
+                vote_sign,
+            }) if sender == self.node_id && round == self.round => {
                 self.add_weight(epoch_id, epoch_hash, vote_sign)?;
             }
             _ => {
@@ -0,0 +1,293 @@
+//! Binary encoding of packet.
+//!
+//! Integers are big-endian, bytes are prefixed with u32 length.
+
+use alloc::vec::Vec;
+
+use crate::{
+    packet::{
+        BroadcastCommit, BroadcastPropose, Heartbeat, NewRound, Packet, ResponsePropose,
+        RoundChange, Voted,
+    },
+    VoteSign,
+};
+
+/// Encode and decode type to bytes.
+pub trait Codec: Sized {
+    /// Append encoded bytes to `out`.
+    fn encode(&self, out: &mut Vec<u8>);
+
+    /// Decode from head of `input`, advance `input`.
+    ///
+    /// Return `None` if bytes is invalid.
+    fn decode(input: &mut &[u8]) -> Option<Self>;
+
+    /// Encode to bytes.
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.encode(&mut out);
+        out
+    }
+
+    /// Decode from bytes, all bytes must be used.
+    fn from_bytes(mut input: &[u8]) -> Option<Self> {
+        let r = Self::decode(&mut input)?;
+
+        if input.is_empty() {
+            Some(r)
+        } else {
+            None
+        }
+    }
+}
+
+fn take<'a>(input: &mut &'a [u8], len: usize) -> Option<&'a [u8]> {
+    if input.len() < len {
+        return None;
+    }
+
+    let (head, tail) = input.split_at(len);
+    *input = tail;
+    Some(head)
+}
+
+macro_rules! impl_codec_for_int {
+    ($($t:ty),*) => {
+        $(
+            impl Codec for $t {
+                fn encode(&self, out: &mut Vec<u8>) {
+                    out.extend_from_slice(&self.to_be_bytes());
+                }
+
+                fn decode(input: &mut &[u8]) -> Option<Self> {
+                    let bytes = take(input, core::mem::size_of::<$t>())?;
+                    Some(<$t>::from_be_bytes(bytes.try_into().ok()?))
+                }
+            }
+        )*
+    };
+}
+
+impl_codec_for_int!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+impl Codec for Vec<u8> {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (self.len() as u32).encode(out);
+        out.extend_from_slice(self);
+    }
+
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        let len = u32::decode(input)? as usize;
+        Some(take(input, len)?.to_vec())
+    }
+}
+
+impl<const N: usize> Codec for [u8; N] {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(self);
+    }
+
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        take(input, N)?.try_into().ok()
+    }
+}
+
+impl<T: Codec> Codec for Option<T> {
+    fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            Some(t) => {
+                out.push(1);
+                t.encode(out);
+            }
+            None => out.push(0),
+        }
+    }
+
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        match u8::decode(input)? {
+            0 => Some(None),
+            1 => Some(Some(T::decode(input)?)),
+            _ => None,
+        }
+    }
+}
+
+impl<S: Codec> Codec for VoteSign<S> {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.idx.encode(out);
+        self.sign.encode(out);
+    }
+
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        Some(Self {
+            idx: u64::decode(input)?,
+            sign: S::decode(input)?,
+        })
+    }
+}
+
+impl<I, H> Codec for Voted<I, H>
+where
+    I: crate::EpochId + Codec,
+    H: crate::EpochHash + Codec,
+{
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.epoch_id.encode(out);
+        self.epoch_hash.encode(out);
+        self.round.encode(out);
+    }
+
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        Some(Self {
+            epoch_id: I::decode(input)?,
+            epoch_hash: H::decode(input)?,
+            round: u64::decode(input)?,
+        })
+    }
+}
+
+impl<I, H, S> Codec for Packet<I, H, S>
+where
+    I: crate::EpochId + Codec,
+    H: crate::EpochHash + Codec,
+    S: crate::Signature + Codec,
+{
+    fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            Packet::BroadcastPropose(p) => {
+                out.push(0);
+                p.epoch_id.encode(out);
+                p.epoch_hash.encode(out);
+                p.round.encode(out);
+                p.vote_sign.encode(out);
+            }
+            Packet::ResponsePropose(p) => {
+                out.push(1);
+                p.epoch_id.encode(out);
+                p.epoch_hash.encode(out);
+                p.round.encode(out);
+                p.vote_sign.encode(out);
+            }
+            Packet::BroadcastCommit(p) => {
+                out.push(2);
+                p.epoch_id.encode(out);
+                p.epoch_hash.encode(out);
+                (p.vote_signs.len() as u32).encode(out);
+                for s in &p.vote_signs {
+                    s.encode(out);
+                }
+            }
+            Packet::RoundChange(p) => {
+                out.push(3);
+                p.epoch_id.encode(out);
+                p.epoch_hash.encode(out);
+                p.round.encode(out);
+                p.voted.encode(out);
+                p.vote_sign.encode(out);
+            }
+            Packet::NewRound(p) => {
+                out.push(4);
+                p.epoch_id.encode(out);
+                p.epoch_hash.encode(out);
+                p.round.encode(out);
+                p.voted.encode(out);
+                (p.vote_signs.len() as u32).encode(out);
+                for s in &p.vote_signs {
+                    s.encode(out);
+                }
+            }
+            Packet::Heartbeat(p) => {
+                out.push(5);
+                p.epoch_id.encode(out);
+                p.epoch_hash.encode(out);
+                p.round.encode(out);
+                p.seq.encode(out);
+            }
+        }
+    }
+
+    fn decode(input: &mut &[u8]) -> Option<Self> {
+        match u8::decode(input)? {
+            0 => Some(Packet::BroadcastPropose(BroadcastPropose {
+                epoch_id: I::decode(input)?,
+                epoch_hash: H::decode(input)?,
+                round: u64::decode(input)?,
+                vote_sign: Option::decode(input)?,
+            })),
+            1 => Some(Packet::ResponsePropose(ResponsePropose {
+                epoch_id: I::decode(input)?,
+                epoch_hash: H::decode(input)?,
+                round: u64::decode(input)?,
+                vote_sign: Option::decode(input)?,
+            })),
+            2 => {
+                let epoch_id = I::decode(input)?;
+                let epoch_hash = H::decode(input)?;
+
+                let len = u32::decode(input)? as usize;
+                // Each sign use at least 8 bytes, avoid allocating from bad length.
+                let mut vote_signs = Vec::with_capacity(len.min(input.len() / 8));
+                for _ in 0..len {
+                    vote_signs.push(VoteSign::decode(input)?);
+                }
+
+                Some(Packet::BroadcastCommit(BroadcastCommit {
+                    epoch_id,
+                    epoch_hash,
+                    vote_signs,
+                }))
+            }
+            3 => Some(Packet::RoundChange(RoundChange {
+                epoch_id: I::decode(input)?,
+                epoch_hash: H::decode(input)?,
+                round: u64::decode(input)?,
+                voted: Option::decode(input)?,
+                vote_sign: Option::decode(input)?,
+            })),
+            4 => {
+                let epoch_id = I::decode(input)?;
+                let epoch_hash = H::decode(input)?;
+                let round = u64::decode(input)?;
+                let voted = Option::decode(input)?;
+
+                let len = u32::decode(input)? as usize;
+                let mut vote_signs = Vec::with_capacity(len.min(input.len() / 8));
+                for _ in 0..len {
+                    vote_signs.push(VoteSign::decode(input)?);
+                }
+
+                Some(Packet::NewRound(NewRound {
+                    epoch_id,
+                    epoch_hash,
+                    round,
+                    voted,
+                    vote_signs,
+                }))
+            }
+            5 => Some(Packet::Heartbeat(Heartbeat {
+                epoch_id: I::decode(input)?,
+                epoch_hash: H::decode(input)?,
+                round: u64::decode(input)?,
+                seq: u64::decode(input)?,
+            })),
+            _ => None,
+        }
+    }
+}
+
+/// Message signed as vote of epoch.
+pub fn vote_message<I: Codec, H: Codec>(epoch_id: &I, epoch_hash: &H) -> Vec<u8> {
+    let mut out = Vec::from(&b"consensus-rs/vote"[..]);
+    epoch_id.encode(&mut out);
+    epoch_hash.encode(&mut out);
+    out
+}
+
+/// Message signed as vote to change round after epoch.
+pub fn round_message<I: Codec, H: Codec>(epoch_id: &I, epoch_hash: &H, round: u64) -> Vec<u8> {
+    let mut out = Vec::from(&b"consensus-rs/round"[..]);
+    epoch_id.encode(&mut out);
+    epoch_hash.encode(&mut out);
+    round.encode(&mut out);
+    out
+}
@@ -12,22 +12,31 @@ pub enum Error {
     /// Lose signature on packet
     NoSignature,
 
+    /// Voter set is empty
+    EmptyVoterSet,
+    /// Voter at index has same id with a previous voter
+    DuplicateVoter(u64),
+    /// Voter at index has zero weight
+    ZeroWeightVoter(u64),
+
     /// Not a error, only timeout
     Timeout,
+    /// Not a error, shutdown requested while waiting packet or timer
+    Shutdown,
 
     /// Error from app
-    AppError(Box<dyn Debug>),
+    AppError(Box<dyn Debug + Send + Sync>),
 
     /// Error from network
-    NetworkError(Box<dyn Debug>),
+    NetworkError(Box<dyn Debug + Send + Sync>),
 }
 
 impl Error {
-    pub(crate) fn app_error(e: impl Debug + 'static) -> Self {
+    pub(crate) fn app_error(e: impl Debug + Send + Sync + 'static) -> Self {
         Self::AppError(Box::new(e))
     }
 
-    pub(crate) fn network_error(e: impl Debug + 'static) -> Self {
+    pub(crate) fn network_error(e: impl Debug + Send + Sync + 'static) -> Self {
         Self::NetworkError(Box::new(e))
     }
 }
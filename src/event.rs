@@ -0,0 +1,74 @@
+//! Event of consensus state transitions.
+
+use alloc::{boxed::Box, string::String};
+
+use crate::{Consensus, Role};
+
+/// Event with consensus triple.
+///
+/// `epoch_id`, `round` and `step` are the state of node when event happened.
+#[derive(Debug, Clone)]
+pub struct Event<N, I, H, W> {
+    pub epoch_id: I,
+    pub round: u64,
+    pub step: u8,
+    pub kind: EventKind<N, I, H, W>,
+}
+
+/// Kind of event.
+#[derive(Debug, Clone)]
+pub enum EventKind<N, I, H, W> {
+    /// Node role changed.
+    RoleChanged { from: Role, to: Role },
+    /// Step changed.
+    StepChanged { from: u8, to: u8 },
+    /// Round changed.
+    RoundChanged { from: u64, to: u64 },
+    /// Round certified by round change votes of a quorum, node moved to it.
+    RoundCertified { round: u64 },
+    /// Timer of step fired.
+    Timeout { role: Role, step: u8 },
+    /// Got a packet, `packet` is the name of packet variant.
+    PacketReceived { sender: N, packet: &'static str },
+    /// Packet dropped by inbound check, `reason` is the check failed.
+    PacketDropped { sender: N, reason: &'static str },
+    /// This node proposed a epoch.
+    Proposed { epoch_id: I, epoch_hash: H },
+    /// Got a proposal from proposer.
+    ProposalReceived {
+        sender: N,
+        epoch_id: I,
+        epoch_hash: H,
+    },
+    /// Got a vote.
+    VoteReceived {
+        idx: u64,
+        epoch_id: I,
+        epoch_hash: H,
+    },
+    /// Vote is counted, `weight` is the accumulated weight.
+    VoteCounted { idx: u64, weight: W },
+    /// Voter already voted, vote is ignored.
+    DuplicateVote { idx: u64 },
+    /// Votes reach quorum, `weight` is the accumulated weight.
+    QuorumReached { weight: W },
+    /// Epoch committed.
+    Committed { epoch_id: I, epoch_hash: H },
+    /// Voter set changed, used from next epoch.
+    VoterSetChanged { joint: bool },
+    /// Error on tick.
+    Error(String),
+}
+
+/// Event of a consensus.
+pub type ConsensusEvent<C> = Event<
+    <C as Consensus>::NodeId,
+    <C as Consensus>::EpochId,
+    <C as Consensus>::EpochHash,
+    <C as Consensus>::Weight,
+>;
+
+/// Callback to receive event.
+///
+/// Listener is `Send`, so engine can be moved to another thread.
+pub type EventListener<C> = Box<dyn FnMut(&ConsensusEvent<C>) + Send>;
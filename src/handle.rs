@@ -0,0 +1,158 @@
+//! Handle to control a running engine.
+
+use core::{
+    fmt::{self, Debug},
+    future::Future,
+    sync::atomic::{AtomicBool, Ordering},
+    task::{Poll, Waker},
+};
+
+use alloc::{collections::VecDeque, sync::Arc};
+use futures_channel::oneshot;
+use spin::Mutex;
+
+use crate::{status::ConsensusStatus, Consensus};
+
+/// Handle to request shutdown of a running engine.
+///
+/// Cheap to clone, all clones control same engine.
+#[derive(Debug, Clone, Default)]
+pub struct ShutdownHandle {
+    flag: Arc<AtomicBool>,
+    waker: Arc<Mutex<Option<Waker>>>,
+}
+
+impl ShutdownHandle {
+    /// Request shutdown.
+    ///
+    /// Engine stops on next wait of packet or timer, or after current tick.
+    pub fn shutdown(&self) {
+        self.flag.store(true, Ordering::Release);
+
+        if let Some(waker) = self.waker.lock().take() {
+            waker.wake();
+        }
+    }
+
+    /// Check shutdown requested.
+    pub fn is_shutdown(&self) -> bool {
+        self.flag.load(Ordering::Acquire)
+    }
+
+    /// Wait until shutdown requested, only one waiter is woken.
+    pub(crate) fn wait(&self) -> impl Future<Output = ()> + '_ {
+        futures_lite::future::poll_fn(move |cx| {
+            *self.waker.lock() = Some(cx.waker().clone());
+
+            if self.is_shutdown() {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+    }
+}
+
+/// Command to engine.
+///
+/// Commands are processed between ticks.
+pub enum Command<C: Consensus> {
+    /// Move to next round, abandon current step.
+    ForceRoundChange,
+    /// Stop or resume proposing epoch when this node is proposer.
+    PauseProposing(bool),
+    /// Give up proposer role on current epoch, start a round change to next proposer.
+    StepDown,
+    /// Load latest voter set from consensus again, applied on next commit.
+    ///
+    /// Only affects this node, send it to every voter for the same epoch, or voter sets diverge.
+    ReloadVoterSet,
+    /// Get status of engine.
+    DumpStatus(oneshot::Sender<ConsensusStatus<C>>),
+}
+
+impl<C: Consensus> Debug for Command<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ForceRoundChange => write!(f, "ForceRoundChange"),
+            Self::PauseProposing(paused) => write!(f, "PauseProposing({})", paused),
+            Self::StepDown => write!(f, "StepDown"),
+            Self::ReloadVoterSet => write!(f, "ReloadVoterSet"),
+            Self::DumpStatus(_) => write!(f, "DumpStatus"),
+        }
+    }
+}
+
+/// Queue of command, shared by engine and handles.
+pub(crate) type CommandQueue<C> = Arc<Mutex<VecDeque<Command<C>>>>;
+
+/// Handle to send command to a running engine.
+///
+/// Cheap to clone, all clones control same engine.
+pub struct ControlHandle<C: Consensus> {
+    queue: CommandQueue<C>,
+    shutdown: ShutdownHandle,
+}
+
+impl<C: Consensus> Clone for ControlHandle<C> {
+    fn clone(&self) -> Self {
+        Self {
+            queue: self.queue.clone(),
+            shutdown: self.shutdown.clone(),
+        }
+    }
+}
+
+impl<C: Consensus> ControlHandle<C> {
+    pub(crate) fn new(queue: CommandQueue<C>, shutdown: ShutdownHandle) -> Self {
+        Self { queue, shutdown }
+    }
+
+    /// Send a command.
+    pub fn send(&self, command: Command<C>) {
+        self.queue.lock().push_back(command);
+    }
+
+    /// Move to next round, abandon current step.
+    pub fn force_round_change(&self) {
+        self.send(Command::<C>::ForceRoundChange)
+    }
+
+    /// Stop proposing epoch.
+    pub fn pause_proposing(&self) {
+        self.send(Command::<C>::PauseProposing(true))
+    }
+
+    /// Resume proposing epoch.
+    pub fn resume_proposing(&self) {
+        self.send(Command::<C>::PauseProposing(false))
+    }
+
+    /// Give up proposer role on current epoch.
+    pub fn step_down(&self) {
+        self.send(Command::<C>::StepDown)
+    }
+
+    /// Load latest voter set from consensus again, applied on next commit.
+    ///
+    /// Only affects this node, see [`Command::ReloadVoterSet`].
+    pub fn reload_voter_set(&self) {
+        self.send(Command::<C>::ReloadVoterSet)
+    }
+
+    /// Get status of engine.
+    ///
+    /// Return `None` if engine dropped before command processed.
+    pub async fn dump_status(&self) -> Option<ConsensusStatus<C>> {
+        let (sender, recver) = oneshot::channel();
+
+        self.send(Command::<C>::DumpStatus(sender));
+
+        recver.await.ok()
+    }
+
+    /// Request shutdown of `run`.
+    pub fn shutdown(&self) {
+        self.shutdown.shutdown()
+    }
+}
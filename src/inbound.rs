@@ -0,0 +1,433 @@
+//! Inbound flood protection.
+//!
+//! Packets are checked before any expensive work. Each sender has a budget of packets on each
+//! round, and gets penalty for invalid packets. Sender over penalty limit is banned until
+//! penalty decays on commit.
+//!
+//! Received packets are queued, packet of sender with less usage on this round is processed
+//! first, so a flooding peer can't starve others.
+//!
+//! Packet for a later epoch or step, like a proposal arrived before commit of previous epoch,
+//! is buffered, and replayed when engine reaches the matching epoch and step.
+//!
+//! Round packets and heartbeat carry the latest committed epoch of sender, they are handled on
+//! the same latest epoch only.
+
+use alloc::{collections::VecDeque, vec::Vec};
+use num_traits::One;
+
+use crate::{packet::Packet, quorum::FaultTolerance, Consensus, Role, VoterConfig};
+
+/// Policy of inbound packets.
+#[derive(Debug, Clone)]
+pub struct InboundPolicy {
+    queue_size: usize,
+    buffer_size: usize,
+    budget: u32,
+    penalty_limit: u32,
+    round_window: u64,
+}
+
+impl Default for InboundPolicy {
+    /// Queue 256 packets, buffer 64 future packets, 32 packets of each sender on each round,
+    /// ban after 8 invalid packets, accept round change up to 16 rounds ahead.
+    fn default() -> Self {
+        Self {
+            queue_size: 256,
+            buffer_size: 64,
+            budget: 32,
+            penalty_limit: 8,
+            round_window: 16,
+        }
+    }
+}
+
+impl InboundPolicy {
+    /// Set max packets in queue.
+    pub fn with_queue_size(mut self, queue_size: usize) -> Self {
+        self.queue_size = queue_size;
+        self
+    }
+
+    /// Set max packets buffered for later epoch or step, oldest is dropped first.
+    pub fn with_buffer_size(mut self, buffer_size: usize) -> Self {
+        self.buffer_size = buffer_size;
+        self
+    }
+
+    /// Set max packets of each sender on each round.
+    pub fn with_budget(mut self, budget: u32) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// Set invalid packets before sender is banned.
+    pub fn with_penalty_limit(mut self, penalty_limit: u32) -> Self {
+        self.penalty_limit = penalty_limit;
+        self
+    }
+
+    /// Set max rounds a round change is ahead of current round.
+    ///
+    /// Votes are kept for each round, so rounds far ahead are dropped.
+    pub fn with_round_window(mut self, round_window: u64) -> Self {
+        self.round_window = round_window;
+        self
+    }
+
+    /// Max packets in queue.
+    pub fn queue_size(&self) -> usize {
+        self.queue_size
+    }
+}
+
+type ConsensusPacket<C> =
+    Packet<<C as Consensus>::EpochId, <C as Consensus>::EpochHash, <C as Consensus>::Signature>;
+
+/// State of engine to check packet.
+pub(crate) struct Context<'a, C: Consensus> {
+    pub latest_epoch_id: &'a C::EpochId,
+    pub epoch_id: &'a C::EpochId,
+    pub role: Role,
+    pub proposer: &'a C::NodeId,
+    pub round: u64,
+    pub step: u8,
+    pub fault_tolerance: FaultTolerance,
+    /// Epochs follower voted and not committed, in order.
+    pub voted: &'a [C::EpochId],
+    /// Max epochs follower votes before commit.
+    pub depth: usize,
+    pub voters: &'a VoterConfig<C::NodeId, C::PublicKey, C::Weight>,
+}
+
+#[derive(Debug)]
+struct Sender<N> {
+    node_id: N,
+    used: u32,
+    penalty: u32,
+}
+
+/// Inbound queue with per-sender budget and penalty.
+pub(crate) struct Inbound<C: Consensus> {
+    policy: InboundPolicy,
+    queue: VecDeque<(ConsensusPacket<C>, C::NodeId)>,
+    buffer: VecDeque<(ConsensusPacket<C>, C::NodeId)>,
+    senders: Vec<Sender<C::NodeId>>,
+}
+
+/// Whether packet can be handled on current state.
+#[derive(Debug, PartialEq, Eq)]
+enum Readiness {
+    Ready,
+    Future,
+    Stale,
+}
+
+impl<C: Consensus> Inbound<C> {
+    pub fn new(policy: InboundPolicy) -> Self {
+        Self {
+            policy,
+            queue: VecDeque::new(),
+            buffer: VecDeque::new(),
+            senders: Vec::new(),
+        }
+    }
+
+    pub fn policy(&self) -> &InboundPolicy {
+        &self.policy
+    }
+
+    fn sender(&mut self, node_id: &C::NodeId) -> &mut Sender<C::NodeId> {
+        let idx = match self.senders.iter().position(|s| &s.node_id == node_id) {
+            Some(idx) => idx,
+            None => {
+                self.senders.push(Sender {
+                    node_id: node_id.clone(),
+                    used: 0,
+                    penalty: 0,
+                });
+                self.senders.len() - 1
+            }
+        };
+
+        &mut self.senders[idx]
+    }
+
+    fn used(&self, node_id: &C::NodeId) -> u32 {
+        self.senders
+            .iter()
+            .find(|s| &s.node_id == node_id)
+            .map(|s| s.used)
+            .unwrap_or(0)
+    }
+
+    /// Check packet, queue it if valid.
+    ///
+    /// Return reason if packet is dropped.
+    pub fn push(
+        &mut self,
+        pkt: ConsensusPacket<C>,
+        sender: C::NodeId,
+        ctx: &Context<'_, C>,
+    ) -> Result<(), &'static str> {
+        let penalty_limit = self.policy.penalty_limit;
+        let budget = self.policy.budget;
+
+        // Entry is only created for voters, so non-voters can't grow it.
+        let (used, penalty) = self
+            .senders
+            .iter()
+            .find(|s| s.node_id == sender)
+            .map_or((0, 0), |s| (s.used, s.penalty));
+
+        if penalty >= penalty_limit {
+            return Err("banned");
+        }
+
+        if let Err((reason, penalty)) = check(&pkt, &sender, ctx) {
+            if penalty && ctx.voters.index_of(&sender).is_some() {
+                self.sender(&sender).penalty += 1;
+            }
+            return Err(reason);
+        }
+
+        // Round change votes are kept until commit, honest node far ahead catches up on commit.
+        if let Packet::RoundChange(p) = &pkt {
+            if p.round > ctx.round.saturating_add(self.policy.round_window) {
+                return Err("far_round");
+            }
+        }
+
+        // Heartbeat of idle proposer isn't limited by round, budget isn't refilled.
+        let charged = !(matches!(pkt, Packet::Heartbeat(_)) && &sender == ctx.proposer);
+
+        if charged && used >= budget {
+            self.sender(&sender).penalty += 1;
+            return Err("over_budget");
+        }
+
+        if self.queue.len() >= self.policy.queue_size {
+            return Err("queue_full");
+        }
+
+        if charged {
+            self.sender(&sender).used += 1;
+        }
+        self.queue.push_back((pkt, sender));
+
+        Ok(())
+    }
+
+    /// Pop packet can be handled on current state.
+    ///
+    /// Buffered packet is replayed first, then packet of sender with least usage on this round.
+    /// Packet for later epoch or step is buffered, stale packet is dropped.
+    pub fn pop(&mut self, ctx: &Context<'_, C>) -> Option<(ConsensusPacket<C>, C::NodeId)> {
+        self.buffer
+            .retain(|(pkt, _)| readiness(pkt, ctx) != Readiness::Stale);
+
+        if let Some(idx) = self
+            .buffer
+            .iter()
+            .position(|(pkt, _)| readiness(pkt, ctx) == Readiness::Ready)
+        {
+            return self.buffer.remove(idx);
+        }
+
+        loop {
+            let idx = (0..self.queue.len()).min_by_key(|i| self.used(&self.queue[*i].1))?;
+            let (pkt, sender) = self.queue.remove(idx)?;
+
+            match readiness(&pkt, ctx) {
+                Readiness::Ready => return Some((pkt, sender)),
+                Readiness::Future => {
+                    log::debug!("Buffer {} from {:?}", pkt.name(), sender);
+
+                    if self.buffer.len() >= self.policy.buffer_size {
+                        if let Some((pkt, sender)) = self.buffer.pop_front() {
+                            log::debug!("Buffer full, drop {} from {:?}", pkt.name(), sender);
+                        }
+                    }
+
+                    self.buffer.push_back((pkt, sender));
+                }
+                Readiness::Stale => {
+                    log::debug!("Drop stale {} from {:?}", pkt.name(), sender);
+                }
+            }
+        }
+    }
+
+    /// Penalize sender of a invalid packet found after queued.
+    pub fn penalize(&mut self, sender: &C::NodeId) {
+        self.sender(sender).penalty += 1;
+    }
+
+    /// Refill budget of all senders.
+    pub fn next_round(&mut self) {
+        for sender in &mut self.senders {
+            sender.used = 0;
+        }
+    }
+
+    /// Decay penalty on commit, forget sender without penalty.
+    pub fn committed(&mut self) {
+        self.next_round();
+
+        for sender in &mut self.senders {
+            sender.penalty /= 2;
+        }
+
+        self.senders.retain(|s| s.penalty > 0);
+    }
+}
+
+fn epoch_id<I: crate::EpochId, H: crate::EpochHash, S: crate::Signature>(
+    pkt: &Packet<I, H, S>,
+) -> &I {
+    match pkt {
+        Packet::BroadcastPropose(p) => &p.epoch_id,
+        Packet::ResponsePropose(p) => &p.epoch_id,
+        Packet::BroadcastCommit(p) => &p.epoch_id,
+        Packet::RoundChange(p) => &p.epoch_id,
+        Packet::NewRound(p) => &p.epoch_id,
+        Packet::Heartbeat(p) => &p.epoch_id,
+    }
+}
+
+/// Packet of committed epoch, late packet from honest node.
+///
+/// Round packets and heartbeat are of latest committed epoch of sender, only stale if sender is
+/// behind.
+fn is_stale<C: Consensus>(pkt: &ConsensusPacket<C>, ctx: &Context<'_, C>) -> bool {
+    match pkt {
+        Packet::RoundChange(_) | Packet::NewRound(_) | Packet::Heartbeat(_) => {
+            epoch_id(pkt) < ctx.latest_epoch_id
+        }
+        _ => epoch_id(pkt) <= ctx.latest_epoch_id,
+    }
+}
+
+fn next_epoch<C: Consensus>(ctx: &Context<'_, C>) -> C::EpochId {
+    ctx.latest_epoch_id.clone() + One::one()
+}
+
+/// Decide packet can be handled on current state.
+fn readiness<C: Consensus>(pkt: &ConsensusPacket<C>, ctx: &Context<'_, C>) -> Readiness {
+    let epoch_id = epoch_id(pkt);
+
+    if is_stale(pkt, ctx) {
+        return Readiness::Stale;
+    }
+
+    let expected = match (ctx.role, ctx.step) {
+        // Votes on proposal of this node.
+        (Role::Proposer, 1) => Some(ctx.epoch_id),
+        // Commit of first voted epoch.
+        (Role::Follower, 1) => ctx.voted.first(),
+        _ => None,
+    };
+
+    // Proposal and vote of next epoch are on a round, later epochs are on round of pipeline.
+    let round = match pkt {
+        Packet::BroadcastPropose(p) if epoch_id == &next_epoch(ctx) => Some(p.round),
+        Packet::ResponsePropose(p) if epoch_id == &next_epoch(ctx) => Some(p.round),
+        _ => None,
+    };
+
+    match pkt {
+        // Vote on proposal of other round.
+        Packet::ResponsePropose(_) if round.is_some_and(|r| r != ctx.round) => Readiness::Stale,
+        // Proposal of later round arrived before this node followed its certificate.
+        Packet::BroadcastPropose(_) if round.is_some_and(|r| r > ctx.round) => Readiness::Future,
+        Packet::BroadcastPropose(_) if round.is_some_and(|r| r < ctx.round) => Readiness::Stale,
+        Packet::BroadcastCommit(_) => match expected {
+            Some(e) if epoch_id > e => Readiness::Future,
+            Some(_) => Readiness::Ready,
+            // Commit of later epoch arrived before commit of next epoch.
+            None if epoch_id > &next_epoch(ctx) => Readiness::Future,
+            None => Readiness::Ready,
+        },
+        Packet::BroadcastPropose(_) if ctx.role.is_follower() => match ctx.voted.last() {
+            _ if ctx.step == 0 => Readiness::Ready,
+            // Proposal of next epoch on pipeline.
+            Some(last) if epoch_id > last && ctx.voted.len() < ctx.depth => Readiness::Ready,
+            // Proposal of next epoch arrived before commit.
+            Some(last) if epoch_id > last => Readiness::Future,
+            _ => Readiness::Stale,
+        },
+        Packet::BroadcastPropose(_) | Packet::ResponsePropose(_) => match expected {
+            Some(e) if epoch_id == e && ctx.role.is_proposer() => Readiness::Ready,
+            Some(e) if epoch_id < e => Readiness::Stale,
+            // Vote arrived before proposing.
+            _ => Readiness::Future,
+        },
+        // Sender committed an epoch this node hasn't.
+        Packet::RoundChange(_) | Packet::NewRound(_) | Packet::Heartbeat(_)
+            if epoch_id > ctx.latest_epoch_id =>
+        {
+            Readiness::Future
+        }
+        Packet::RoundChange(_) | Packet::NewRound(_) | Packet::Heartbeat(_) => Readiness::Ready,
+    }
+}
+
+/// Cheap check of packet, return reason and whether sender is penalized.
+fn check<C: Consensus>(
+    pkt: &ConsensusPacket<C>,
+    sender: &C::NodeId,
+    ctx: &Context<'_, C>,
+) -> Result<(), (&'static str, bool)> {
+    let idx = ctx.voters.index_of(sender).ok_or(("not_voter", true))?;
+
+    // Late packet from honest node.
+    if is_stale(pkt, ctx) {
+        return Err(("stale", false));
+    }
+
+    match pkt {
+        Packet::BroadcastPropose(p) => {
+            if p.vote_sign.as_ref().is_some_and(|s| s.idx != idx) {
+                return Err(("bad_vote", true));
+            }
+        }
+        Packet::ResponsePropose(p) => {
+            let sign = p.vote_sign.as_ref().ok_or(("no_signature", true))?;
+
+            if sign.idx != idx {
+                return Err(("bad_vote", true));
+            }
+        }
+        Packet::BroadcastCommit(p) => {
+            let idxs: Vec<u64> = p.vote_signs.iter().map(|s| s.idx).collect();
+
+            // Commit must carry signatures of a quorum.
+            if !ctx.voters.has_quorum(&idxs, ctx.fault_tolerance) {
+                return Err(("no_quorum", true));
+            }
+        }
+        Packet::RoundChange(p) => {
+            let sign = p.vote_sign.as_ref().ok_or(("no_signature", true))?;
+
+            if sign.idx != idx {
+                return Err(("bad_vote", true));
+            }
+        }
+        Packet::NewRound(p) => {
+            let idxs: Vec<u64> = p.vote_signs.iter().map(|s| s.idx).collect();
+
+            // New round must carry round change votes of a quorum.
+            if !ctx.voters.has_quorum(&idxs, ctx.fault_tolerance) {
+                return Err(("no_quorum", true));
+            }
+        }
+        Packet::Heartbeat(_) => {}
+    }
+
+    // Observer only follows commits.
+    if ctx.role.is_observer() && !matches!(pkt, Packet::BroadcastCommit(_)) {
+        return Err(("ignored", false));
+    }
+
+    Ok(())
+}
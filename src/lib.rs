@@ -2,14 +2,42 @@
 
 extern crate alloc;
 
+#[cfg(feature = "std")]
+extern crate std;
+
 mod prelude;
 pub use prelude::*;
 
 pub mod packet;
 
+pub mod event;
+
+pub mod status;
+
+pub mod handle;
+
+pub mod timeout;
+
+pub mod inbound;
+
+pub mod codec;
+
+pub mod network;
+
+#[cfg(feature = "std")]
+pub mod runtime;
+
+#[cfg(feature = "metrics")]
+pub mod metrics;
+
+pub mod quorum;
+
 mod types;
 pub use types::*;
 
+mod voter_set;
+pub use voter_set::*;
+
 pub mod algorithm;
 
 mod error;
@@ -0,0 +1,308 @@
+//! Metrics of consensus engine.
+//!
+//! Collect metrics from event stream, render in Prometheus text exposition format.
+
+use core::fmt::Write;
+
+use alloc::{
+    collections::{BTreeMap, VecDeque},
+    format,
+    string::String,
+    vec::Vec,
+};
+use num_traits::ToPrimitive;
+use std::{
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use crate::{
+    event::{ConsensusEvent, EventKind},
+    Consensus, Role,
+};
+
+const LATENCY_BUCKETS: &[f64] = &[0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+const ROUND_BUCKETS: &[f64] = &[1.0, 2.0, 3.0, 5.0, 10.0, 20.0];
+
+/// Max epochs in flight with start time, oldest is dropped first.
+const MAX_EPOCHS_STARTED: usize = 16;
+
+#[derive(Debug, Clone)]
+struct Histogram {
+    buckets: &'static [f64],
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn new(buckets: &'static [f64]) -> Self {
+        Self {
+            buckets,
+            counts: alloc::vec![0; buckets.len()],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    fn observe(&mut self, value: f64) {
+        for (bucket, count) in self.buckets.iter().zip(self.counts.iter_mut()) {
+            if value <= *bucket {
+                *count += 1;
+            }
+        }
+
+        self.sum += value;
+        self.count += 1;
+    }
+
+    fn render(&self, out: &mut String, name: &str, help: &str) {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} histogram", name);
+
+        for (bucket, count) in self.buckets.iter().zip(self.counts.iter()) {
+            let _ = writeln!(out, "{}_bucket{{le=\"{}\"}} {}", name, bucket, count);
+        }
+
+        let _ = writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, self.count);
+        let _ = writeln!(out, "{}_sum {}", name, self.sum);
+        let _ = writeln!(out, "{}_count {}", name, self.count);
+    }
+}
+
+#[derive(Debug)]
+struct Inner {
+    /// Start time of each epoch in flight, keyed by debug format of epoch id.
+    epoch_start: VecDeque<(String, Instant)>,
+    rounds: u64,
+
+    commit_latency: Histogram,
+    rounds_per_epoch: Histogram,
+    commits: u64,
+    timeouts: BTreeMap<(&'static str, u8), u64>,
+    votes_received: u64,
+    votes_counted: u64,
+    votes_duplicate: u64,
+    quorum_weight: f64,
+    packets: BTreeMap<&'static str, u64>,
+    dropped: BTreeMap<&'static str, u64>,
+    errors: u64,
+}
+
+/// Metrics of consensus engine.
+///
+/// Cheap to clone, all clones share same metrics.
+/// Subscribe `listener` to engine, then call `render` on scraping.
+#[derive(Debug, Clone)]
+pub struct Metrics {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metrics {
+    /// Build empty metrics.
+    pub fn new() -> Self {
+        let inner = Inner {
+            epoch_start: VecDeque::new(),
+            rounds: 0,
+            commit_latency: Histogram::new(LATENCY_BUCKETS),
+            rounds_per_epoch: Histogram::new(ROUND_BUCKETS),
+            commits: 0,
+            timeouts: BTreeMap::new(),
+            votes_received: 0,
+            votes_counted: 0,
+            votes_duplicate: 0,
+            quorum_weight: 0.0,
+            packets: BTreeMap::new(),
+            dropped: BTreeMap::new(),
+            errors: 0,
+        };
+
+        Self {
+            inner: Arc::new(Mutex::new(inner)),
+        }
+    }
+
+    /// Build event listener to record metrics.
+    pub fn listener<C>(&self) -> impl FnMut(&ConsensusEvent<C>) + Send + 'static
+    where
+        C: Consensus,
+        C::Weight: ToPrimitive,
+    {
+        let metrics = self.clone();
+
+        move |event| metrics.record::<C>(event)
+    }
+
+    /// Record a event.
+    pub fn record<C>(&self, event: &ConsensusEvent<C>)
+    where
+        C: Consensus,
+        C::Weight: ToPrimitive,
+    {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+
+        match &event.kind {
+            EventKind::Proposed { epoch_id, .. } | EventKind::ProposalReceived { epoch_id, .. } => {
+                let key = format!("{:?}", epoch_id);
+
+                // Re-proposal on later round keeps first start time.
+                if !inner.epoch_start.iter().any(|(k, _)| k == &key) {
+                    if inner.epoch_start.len() >= MAX_EPOCHS_STARTED {
+                        inner.epoch_start.pop_front();
+                    }
+                    inner.epoch_start.push_back((key, Instant::now()));
+                }
+            }
+            EventKind::RoundChanged { to, .. } if *to != 0 => inner.rounds += 1,
+            EventKind::Timeout { role, step } => {
+                let role = match role {
+                    Role::Proposer => "proposer",
+                    Role::Follower => "follower",
+                    Role::Observer => "observer",
+                };
+                *inner.timeouts.entry((role, *step)).or_default() += 1;
+            }
+            EventKind::PacketReceived { packet, .. } => {
+                *inner.packets.entry(packet).or_default() += 1;
+            }
+            EventKind::PacketDropped { reason, .. } => {
+                *inner.dropped.entry(reason).or_default() += 1;
+            }
+            EventKind::VoteReceived { .. } => inner.votes_received += 1,
+            EventKind::VoteCounted { .. } => inner.votes_counted += 1,
+            EventKind::DuplicateVote { .. } => inner.votes_duplicate += 1,
+            EventKind::QuorumReached { weight } => {
+                inner.quorum_weight = weight.to_f64().unwrap_or(f64::NAN);
+            }
+            EventKind::Committed { epoch_id, .. } => {
+                let now = Instant::now();
+                let key = format!("{:?}", epoch_id);
+
+                if let Some(idx) = inner.epoch_start.iter().position(|(k, _)| k == &key) {
+                    if let Some((_, start)) = inner.epoch_start.remove(idx) {
+                        let latency = now.duration_since(start).as_secs_f64();
+                        inner.commit_latency.observe(latency);
+                    }
+                }
+
+                let rounds = inner.rounds + 1;
+                inner.rounds_per_epoch.observe(rounds as f64);
+                inner.rounds = 0;
+                inner.commits += 1;
+            }
+            EventKind::Error(_) => inner.errors += 1,
+            _ => {}
+        }
+    }
+
+    /// Render metrics in Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+
+        let mut out = String::new();
+
+        inner.commit_latency.render(
+            &mut out,
+            "consensus_commit_latency_seconds",
+            "Latency from proposal to commit of epoch.",
+        );
+        inner.rounds_per_epoch.render(
+            &mut out,
+            "consensus_rounds_per_epoch",
+            "Rounds used to commit epoch.",
+        );
+
+        render_counter(
+            &mut out,
+            "consensus_commits_total",
+            "Committed epochs.",
+            inner.commits,
+        );
+
+        let _ = writeln!(
+            out,
+            "# HELP consensus_timeouts_total Timeouts of step timer."
+        );
+        let _ = writeln!(out, "# TYPE consensus_timeouts_total counter");
+        for ((role, step), count) in &inner.timeouts {
+            let _ = writeln!(
+                out,
+                "consensus_timeouts_total{{role=\"{}\",step=\"{}\"}} {}",
+                role, step, count
+            );
+        }
+
+        render_counter(
+            &mut out,
+            "consensus_votes_received_total",
+            "Votes received.",
+            inner.votes_received,
+        );
+        render_counter(
+            &mut out,
+            "consensus_votes_counted_total",
+            "Votes counted to weight.",
+            inner.votes_counted,
+        );
+        render_counter(
+            &mut out,
+            "consensus_votes_duplicate_total",
+            "Duplicate votes ignored.",
+            inner.votes_duplicate,
+        );
+
+        let _ = writeln!(
+            out,
+            "# HELP consensus_quorum_weight Weight of latest quorum."
+        );
+        let _ = writeln!(out, "# TYPE consensus_quorum_weight gauge");
+        let _ = writeln!(out, "consensus_quorum_weight {}", inner.quorum_weight);
+
+        let _ = writeln!(
+            out,
+            "# HELP consensus_packets_received_total Packets received by variant."
+        );
+        let _ = writeln!(out, "# TYPE consensus_packets_received_total counter");
+        for (packet, count) in &inner.packets {
+            let _ = writeln!(
+                out,
+                "consensus_packets_received_total{{packet=\"{}\"}} {}",
+                packet, count
+            );
+        }
+
+        let _ = writeln!(
+            out,
+            "# HELP consensus_packets_dropped_total Packets dropped by inbound check."
+        );
+        let _ = writeln!(out, "# TYPE consensus_packets_dropped_total counter");
+        for (reason, count) in &inner.dropped {
+            let _ = writeln!(
+                out,
+                "consensus_packets_dropped_total{{reason=\"{}\"}} {}",
+                reason, count
+            );
+        }
+
+        render_counter(
+            &mut out,
+            "consensus_errors_total",
+            "Errors on tick.",
+            inner.errors,
+        );
+
+        out
+    }
+}
+
+fn render_counter(out: &mut String, name: &str, help: &str, value: u64) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} counter", name);
+    let _ = writeln!(out, "{} {}", name, value);
+}
@@ -0,0 +1,278 @@
+//! Voter-first broadcast with observer fan-out.
+//!
+//! Broadcast packet is sent to each voter first. Only commits are sent to observers, after the
+//! voting path: commit of broadcaster right after the voters, relayed commit on next send or
+//! when engine waits for next packet.
+//!
+//! Scope is limited:
+//! - Delivery is only tracked up to hand-off to inner network, see [`Handoff`]. Observers don't
+//!   acknowledge commits, and lost commits aren't sent again.
+//! - Inner network must reach observers by node id. `TcpTransport` only dials voters, commits to
+//!   observers are dropped over it, use a transport which reaches all nodes instead.
+
+use core::{future::Future, pin::Pin};
+
+use alloc::{boxed::Box, collections::VecDeque, sync::Arc, vec::Vec};
+
+use crate::{packet::Packet, Consensus, Network, VoteSign, VoterConfig};
+
+type Voters<C> =
+    VoterConfig<<C as Consensus>::NodeId, <C as Consensus>::PublicKey, <C as Consensus>::Weight>;
+
+type ConsensusPacket<C> =
+    Packet<<C as Consensus>::EpochId, <C as Consensus>::EpochHash, <C as Consensus>::Signature>;
+
+/// How commits reach observers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Fanout {
+    /// Broadcaster sends commits to all observers.
+    #[default]
+    Direct,
+    /// Each voter relays commits it received to its share of observers.
+    ///
+    /// Each observer is served by `redundancy` voters.
+    Relay { redundancy: usize },
+}
+
+/// Packets handed to inner network for a peer.
+///
+/// Peer doesn't acknowledge them, a packet may still be lost after hand-off.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Handoff<N, I> {
+    pub node_id: N,
+    /// Packets sent.
+    pub sent: u64,
+    /// Latest commit sent.
+    pub commit: Option<I>,
+}
+
+struct State<C: Consensus> {
+    node_id: C::NodeId,
+    voters: Vec<C::NodeId>,
+    observers: Vec<C::NodeId>,
+    fanout: Fanout,
+    pending: VecDeque<(C::NodeId, ConsensusPacket<C>)>,
+    relayed: Option<C::EpochId>,
+    handoffs: Vec<Handoff<C::NodeId, C::EpochId>>,
+}
+
+impl<C: Consensus> State<C> {
+    /// Observers not in voter set.
+    fn observers(&self) -> impl Iterator<Item = &C::NodeId> {
+        self.observers.iter().filter(|o| !self.voters.contains(o))
+    }
+
+    /// Observers this node relays commits to.
+    fn relay_targets(&self, redundancy: usize) -> Vec<C::NodeId> {
+        let n = self.voters.len();
+
+        let idx = match self.voters.iter().position(|v| v == &self.node_id) {
+            Some(idx) => idx,
+            None => return Vec::new(),
+        };
+
+        self.observers()
+            .enumerate()
+            .filter(|(j, _)| (0..redundancy.min(n)).any(|k| (j + k) % n == idx))
+            .map(|(_, o)| o.clone())
+            .collect()
+    }
+
+    fn queue_commit(&mut self, pkt: &ConsensusPacket<C>, targets: Vec<C::NodeId>) {
+        for target in targets {
+            self.pending.push_back((target, pkt.clone()));
+        }
+    }
+
+    fn on_recv(&mut self, pkt: &ConsensusPacket<C>) {
+        let redundancy = match (self.fanout, pkt) {
+            (Fanout::Relay { redundancy }, Packet::BroadcastCommit(p)) => {
+                if self.relayed.as_ref().is_some_and(|r| r >= &p.epoch_id) {
+                    return;
+                }
+                self.relayed = Some(p.epoch_id.clone());
+                redundancy
+            }
+            _ => return,
+        };
+
+        let targets = self.relay_targets(redundancy);
+        self.queue_commit(pkt, targets);
+    }
+
+    fn handed_off(&mut self, target: &C::NodeId, pkt: &ConsensusPacket<C>) {
+        let idx = match self.handoffs.iter().position(|h| &h.node_id == target) {
+            Some(idx) => idx,
+            None => {
+                self.handoffs.push(Handoff {
+                    node_id: target.clone(),
+                    sent: 0,
+                    commit: None,
+                });
+                self.handoffs.len() - 1
+            }
+        };
+
+        let handoff = &mut self.handoffs[idx];
+        handoff.sent += 1;
+
+        if let Packet::BroadcastCommit(p) = pkt {
+            if handoff.commit.as_ref().is_none_or(|c| c < &p.epoch_id) {
+                handoff.commit = Some(p.epoch_id.clone());
+            }
+        }
+    }
+}
+
+/// Network with voter-first broadcast.
+///
+/// Inner network must be able to send to each node, include observers. There is no
+/// acknowledge from peers, [`handoffs`](Self::handoffs) only counts packets handed to it.
+pub struct BroadcastNetwork<C: Consensus, N> {
+    inner: N,
+    state: Arc<spin::Mutex<State<C>>>,
+}
+
+impl<C, N> BroadcastNetwork<C, N>
+where
+    C: Consensus,
+    N: Network<C>,
+{
+    /// Build broadcast layer over network, with observers to send commits.
+    pub fn new(inner: N, observers: Vec<C::NodeId>, fanout: Fanout) -> Self {
+        let state = State {
+            node_id: inner.node_id(),
+            voters: Vec::new(),
+            observers,
+            fanout,
+            pending: VecDeque::new(),
+            relayed: None,
+            handoffs: Vec::new(),
+        };
+
+        Self {
+            inner,
+            state: Arc::new(spin::Mutex::new(state)),
+        }
+    }
+
+    /// Inner network.
+    pub fn inner(&self) -> &N {
+        &self.inner
+    }
+
+    /// Add a observer.
+    pub fn add_observer(&self, node_id: C::NodeId) {
+        let mut state = self.state.lock();
+
+        if !state.observers.contains(&node_id) {
+            state.observers.push(node_id);
+        }
+    }
+
+    /// Remove a observer.
+    pub fn remove_observer(&self, node_id: &C::NodeId) {
+        self.state.lock().observers.retain(|o| o != node_id);
+    }
+
+    /// Packets handed to inner network for each peer.
+    pub fn handoffs(&self) -> Vec<Handoff<C::NodeId, C::EpochId>> {
+        self.state.lock().handoffs.clone()
+    }
+
+    /// Send pending commits to observers.
+    pub fn flush(&self) {
+        loop {
+            // Don't hold lock when sending.
+            let next = self.state.lock().pending.pop_front();
+
+            let (target, pkt) = match next {
+                Some(next) => next,
+                None => break,
+            };
+
+            self.state.lock().handed_off(&target, &pkt);
+            self.inner.send_unsigned(Some(target), pkt);
+        }
+    }
+}
+
+impl<C, N> Network<C> for BroadcastNetwork<C, N>
+where
+    C: Consensus + 'static,
+    N: Network<C>,
+    N::RecvFuture: 'static,
+{
+    type Error = N::Error;
+
+    fn node_id(&self) -> C::NodeId {
+        self.inner.node_id()
+    }
+
+    fn send_unsigned(&self, target: Option<C::NodeId>, pkt: ConsensusPacket<C>) {
+        if let Some(target) = target {
+            self.state.lock().handed_off(&target, &pkt);
+            self.inner.send_unsigned(Some(target), pkt);
+            self.flush();
+            return;
+        }
+
+        let voters = self.state.lock().voters.clone();
+
+        for voter in voters {
+            self.state.lock().handed_off(&voter, &pkt);
+            self.inner.send_unsigned(Some(voter), pkt.clone());
+        }
+
+        {
+            let mut state = self.state.lock();
+
+            if state.fanout == Fanout::Direct && matches!(pkt, Packet::BroadcastCommit(_)) {
+                let observers = state.observers().cloned().collect();
+                state.queue_commit(&pkt, observers);
+            }
+        }
+
+        self.flush();
+    }
+
+    type RecvFuture =
+        Pin<Box<dyn Future<Output = Result<(ConsensusPacket<C>, C::NodeId), N::Error>>>>;
+
+    fn recv(&self) -> Self::RecvFuture {
+        self.flush();
+
+        let recv = self.inner.recv();
+        let state = self.state.clone();
+
+        Box::pin(async move {
+            let (pkt, sender) = recv.await?;
+            state.lock().on_recv(&pkt);
+            Ok((pkt, sender))
+        })
+    }
+
+    fn sign_vote(
+        &self,
+        epoch_id: &C::EpochId,
+        epoch_hash: &C::EpochHash,
+    ) -> Option<VoteSign<C::Signature>> {
+        self.inner.sign_vote(epoch_id, epoch_hash)
+    }
+
+    fn verify_votes(&self, pkt: &ConsensusPacket<C>) -> bool {
+        self.inner.verify_votes(pkt)
+    }
+
+    fn update_voters(&mut self, voters: &Voters<C>) {
+        let ids = voters
+            .voters()
+            .into_iter()
+            .map(|v| v.voter_id.clone())
+            .collect();
+
+        self.state.lock().voters = ids;
+
+        self.inner.update_voters(voters);
+    }
+}
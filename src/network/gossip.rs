@@ -0,0 +1,438 @@
+//! Gossip relay over raw transport.
+//!
+//! Message is relayed to a few peers until its TTL runs out, so it reaches nodes not directly
+//! connected. Each frame is TTL, optional target node and payload. Duplicate frames are
+//! suppressed by a bounded cache of payload digest, keyed by a secret of this node so peers
+//! can't craft colliding frames.
+
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Poll, Waker},
+};
+
+use alloc::{
+    boxed::Box,
+    collections::{BTreeSet, VecDeque},
+    sync::Arc,
+    vec::Vec,
+};
+
+use futures_lite::future;
+
+use crate::{codec::Codec, Consensus, Transport, VoterConfig};
+
+type Voters<C> =
+    VoterConfig<<C as Consensus>::NodeId, <C as Consensus>::PublicKey, <C as Consensus>::Weight>;
+
+/// Config of gossip.
+#[derive(Debug, Clone)]
+pub struct GossipConfig {
+    fanout: usize,
+    ttl: u8,
+    cache_size: usize,
+    key: Option<(u64, u64)>,
+}
+
+impl Default for GossipConfig {
+    /// Relay to 3 peers for 6 hops, remember 4096 messages.
+    fn default() -> Self {
+        Self {
+            fanout: 3,
+            ttl: 6,
+            cache_size: 4096,
+            key: None,
+        }
+    }
+}
+
+impl GossipConfig {
+    /// Set peers each message is relayed to.
+    pub fn with_fanout(mut self, fanout: usize) -> Self {
+        self.fanout = fanout;
+        self
+    }
+
+    /// Set max hops of message.
+    pub fn with_ttl(mut self, ttl: u8) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Set max digests in seen cache.
+    pub fn with_cache_size(mut self, cache_size: usize) -> Self {
+        self.cache_size = cache_size;
+        self
+    }
+
+    /// Set secret key of digest, it should be random and kept private.
+    ///
+    /// Without it, key is random with feature `std`, or derived from memory address.
+    pub fn with_key(mut self, k0: u64, k1: u64) -> Self {
+        self.key = Some((k0, k1));
+        self
+    }
+}
+
+/// Bounded set of message digest, oldest is evicted first.
+#[derive(Debug)]
+struct SeenCache {
+    digests: BTreeSet<u64>,
+    order: VecDeque<u64>,
+    size: usize,
+}
+
+impl SeenCache {
+    fn new(size: usize) -> Self {
+        Self {
+            digests: BTreeSet::new(),
+            order: VecDeque::new(),
+            size,
+        }
+    }
+
+    /// Return false if digest already seen.
+    fn insert(&mut self, digest: u64) -> bool {
+        if !self.digests.insert(digest) {
+            return false;
+        }
+
+        self.order.push_back(digest);
+
+        while self.order.len() > self.size {
+            if let Some(d) = self.order.pop_front() {
+                self.digests.remove(&d);
+            }
+        }
+
+        true
+    }
+}
+
+fn sip_round(v: &mut [u64; 4]) {
+    v[0] = v[0].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(13) ^ v[0];
+    v[0] = v[0].rotate_left(32);
+    v[2] = v[2].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(16) ^ v[2];
+    v[0] = v[0].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(21) ^ v[0];
+    v[2] = v[2].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(17) ^ v[2];
+    v[2] = v[2].rotate_left(32);
+}
+
+/// SipHash-2-4 of bytes.
+fn digest(key: (u64, u64), bytes: &[u8]) -> u64 {
+    let mut v = [
+        key.0 ^ 0x736f_6d65_7073_6575,
+        key.1 ^ 0x646f_7261_6e64_6f6d,
+        key.0 ^ 0x6c79_6765_6e65_7261,
+        key.1 ^ 0x7465_6462_7974_6573,
+    ];
+
+    let compress = |v: &mut [u64; 4], m: u64| {
+        v[3] ^= m;
+        sip_round(v);
+        sip_round(v);
+        v[0] ^= m;
+    };
+
+    let mut chunks = bytes.chunks_exact(8);
+    for chunk in &mut chunks {
+        let mut m = [0u8; 8];
+        m.copy_from_slice(chunk);
+        compress(&mut v, u64::from_le_bytes(m));
+    }
+
+    let last = chunks
+        .remainder()
+        .iter()
+        .enumerate()
+        .fold((bytes.len() as u64) << 56, |m, (i, b)| {
+            m | (*b as u64) << (8 * i)
+        });
+    compress(&mut v, last);
+
+    v[2] ^= 0xff;
+    for _ in 0..4 {
+        sip_round(&mut v);
+    }
+
+    v[0] ^ v[1] ^ v[2] ^ v[3]
+}
+
+#[cfg(feature = "std")]
+fn random_key<T>(_shared: &T) -> (u64, u64) {
+    use std::hash::BuildHasher;
+
+    let state = std::collections::hash_map::RandomState::new();
+
+    (state.hash_one(0u8), state.hash_one(1u8))
+}
+
+#[cfg(not(feature = "std"))]
+fn random_key<T>(shared: &T) -> (u64, u64) {
+    let addr = shared as *const T as usize as u64;
+
+    (digest((addr, !addr), b"k0"), digest((!addr, addr), b"k1"))
+}
+
+struct Shared<C: Consensus> {
+    node_id: C::NodeId,
+    peers: Vec<C::NodeId>,
+    seen: SeenCache,
+    key: (u64, u64),
+    local: VecDeque<Vec<u8>>,
+    /// Waker of `recv` waiting, woken on local message.
+    waker: Option<Waker>,
+    config: GossipConfig,
+}
+
+impl<C> Shared<C>
+where
+    C: Consensus,
+    C::NodeId: Codec,
+{
+    /// Pick `fanout` peers, start from a position decided by digest.
+    fn pick(&self, digest: u64) -> Vec<C::NodeId> {
+        let n = self.peers.len();
+
+        if n == 0 {
+            return Vec::new();
+        }
+
+        let start = (digest % n as u64) as usize;
+
+        (0..self.config.fanout.min(n))
+            .map(|i| self.peers[(start + i) % n].clone())
+            .collect()
+    }
+
+    fn digest(&self, bytes: &[u8]) -> u64 {
+        digest(self.key, bytes)
+    }
+
+    /// Queue message delivered to this node, wake `recv`.
+    fn push_local(&mut self, msg: Vec<u8>) {
+        self.local.push_back(msg);
+
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+fn encode_frame<N: Codec>(ttl: u8, target: &Option<N>, payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(payload.len() + 16);
+    ttl.encode(&mut out);
+    target.encode(&mut out);
+    out.extend_from_slice(payload);
+    out
+}
+
+type Recver<C, T> = Pin<Box<<T as Transport<C>>::RecvFuture>>;
+
+/// Transport relaying messages by gossip.
+///
+/// Peers are directly connected nodes. If no peer is set, voters are used.
+pub struct GossipTransport<C: Consensus, T: Transport<C>> {
+    transport: Arc<T>,
+    shared: Arc<spin::Mutex<Shared<C>>>,
+    /// Receive of transport in flight, kept when local message is returned first.
+    recver: Arc<spin::Mutex<Option<Recver<C, T>>>>,
+    fixed_peers: bool,
+}
+
+/// Message returned first by `recv`.
+enum Source<E> {
+    Local(Vec<u8>),
+    Remote(Result<Vec<u8>, E>),
+}
+
+impl<C, T> GossipTransport<C, T>
+where
+    C: Consensus,
+    C::NodeId: Codec,
+    T: Transport<C>,
+{
+    /// Build gossip over transport, with directly connected peers.
+    pub fn new(
+        node_id: C::NodeId,
+        transport: T,
+        peers: Vec<C::NodeId>,
+        config: GossipConfig,
+    ) -> Self {
+        let fixed_peers = !peers.is_empty();
+
+        let shared = Shared {
+            seen: SeenCache::new(config.cache_size),
+            key: config.key.unwrap_or_default(),
+            node_id,
+            peers,
+            local: VecDeque::new(),
+            waker: None,
+            config,
+        };
+        let shared = Arc::new(spin::Mutex::new(shared));
+
+        {
+            let mut locked = shared.lock();
+            if locked.config.key.is_none() {
+                locked.key = random_key(&*shared);
+            }
+        }
+
+        Self {
+            transport: Arc::new(transport),
+            shared,
+            recver: Arc::new(spin::Mutex::new(None)),
+            fixed_peers,
+        }
+    }
+
+    /// Underline transport.
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+}
+
+impl<C, T> Transport<C> for GossipTransport<C, T>
+where
+    C: Consensus + 'static,
+    C::NodeId: Codec,
+    T: Transport<C> + 'static,
+{
+    type Error = T::Error;
+
+    fn send(&self, target: Option<C::NodeId>, msg: Vec<u8>) {
+        let mut shared = self.shared.lock();
+
+        if target.as_ref().is_none_or(|t| t == &shared.node_id) {
+            shared.push_local(msg.clone());
+        }
+
+        if target.as_ref() == Some(&shared.node_id) {
+            return;
+        }
+
+        let frame = encode_frame(shared.config.ttl, &target, &msg);
+        let digest = shared.digest(&frame[1..]);
+        shared.seen.insert(digest);
+
+        let peers = match &target {
+            // Send to target directly if connected.
+            Some(t) if shared.peers.contains(t) => alloc::vec![t.clone()],
+            _ => shared.pick(digest),
+        };
+
+        drop(shared);
+
+        for peer in peers {
+            self.transport.send(Some(peer), frame.clone());
+        }
+    }
+
+    type RecvFuture = Pin<Box<dyn Future<Output = Result<Vec<u8>, T::Error>>>>;
+
+    fn recv(&self) -> Self::RecvFuture {
+        let transport = self.transport.clone();
+        let shared = self.shared.clone();
+        let recver = self.recver.clone();
+
+        Box::pin(async move {
+            loop {
+                let mut remote = recver
+                    .lock()
+                    .take()
+                    .unwrap_or_else(|| Box::pin(transport.recv()));
+
+                // Local message first, then frame from transport.
+                let local = future::poll_fn(|cx| {
+                    let mut shared = shared.lock();
+
+                    match shared.local.pop_front() {
+                        Some(msg) => Poll::Ready(Source::Local(msg)),
+                        None => {
+                            shared.waker = Some(cx.waker().clone());
+                            Poll::Pending
+                        }
+                    }
+                });
+
+                let source = future::or(local, async { Source::Remote((&mut remote).await) }).await;
+
+                let frame = match source {
+                    Source::Local(msg) => {
+                        *recver.lock() = Some(remote);
+                        return Ok(msg);
+                    }
+                    Source::Remote(frame) => frame?,
+                };
+
+                let mut input = &frame[..];
+                let ttl = u8::decode(&mut input);
+                let target = Option::<C::NodeId>::decode(&mut input);
+
+                let (ttl, target) = match (ttl, target) {
+                    (Some(ttl), Some(target)) => (ttl, target),
+                    _ => {
+                        log::debug!("Drop invalid gossip frame of {} bytes", frame.len());
+                        continue;
+                    }
+                };
+
+                let mut shared = shared.lock();
+
+                let digest = shared.digest(&frame[1..]);
+                if !shared.seen.insert(digest) {
+                    continue;
+                }
+
+                let for_me = target.as_ref().is_none_or(|t| t == &shared.node_id);
+
+                // Unicast reached target, stop relaying.
+                let peers = if ttl == 0 || target.as_ref() == Some(&shared.node_id) {
+                    Vec::new()
+                } else {
+                    match &target {
+                        Some(t) if shared.peers.contains(t) => alloc::vec![t.clone()],
+                        _ => shared.pick(digest),
+                    }
+                };
+
+                drop(shared);
+
+                if !peers.is_empty() {
+                    let frame = encode_frame(ttl - 1, &target, input);
+
+                    for peer in peers {
+                        transport.send(Some(peer), frame.clone());
+                    }
+                }
+
+                if for_me {
+                    return Ok(input.to_vec());
+                }
+            }
+        })
+    }
+
+    fn update_voters(&self, voters: &Voters<C>) {
+        if !self.fixed_peers {
+            let mut shared = self.shared.lock();
+            let node_id = shared.node_id.clone();
+
+            let mut peers: Vec<C::NodeId> = voters
+                .voters()
+                .into_iter()
+                .map(|v| v.voter_id.clone())
+                .collect();
+
+            peers.retain(|p| p != &node_id);
+            shared.peers = peers;
+        }
+
+        self.transport.update_voters(voters);
+    }
+}
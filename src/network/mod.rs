@@ -0,0 +1,13 @@
+//! Networks for node.
+
+#[cfg(feature = "tcp")]
+pub mod tcp;
+
+mod signed;
+pub use signed::*;
+
+mod broadcast;
+pub use broadcast::*;
+
+mod gossip;
+pub use gossip::*;
@@ -0,0 +1,272 @@
+//! Network signing packets over raw transport.
+//!
+//! Each message is encoded as sender node id, packet and signature of both. On receive, sender
+//! must be in voter set and signature of message is verified, invalid message is dropped. Vote
+//! signatures in packet are verified later by `Network::verify_votes`, only on packet passed
+//! inbound checks of engine.
+
+use core::{future::Future, pin::Pin};
+
+use alloc::{boxed::Box, sync::Arc, vec::Vec};
+
+use crate::{
+    codec::{round_message, vote_message, Codec},
+    packet::Packet,
+    Consensus, Network, Signer, Transport, VoteSign, VoterConfig,
+};
+
+type Voters<C> =
+    VoterConfig<<C as Consensus>::NodeId, <C as Consensus>::PublicKey, <C as Consensus>::Weight>;
+
+type ConsensusPacket<C> =
+    Packet<<C as Consensus>::EpochId, <C as Consensus>::EpochHash, <C as Consensus>::Signature>;
+
+/// Sign vote of this node, `None` if node isn't voter.
+fn vote_sign<C, S>(
+    voters: Option<&Voters<C>>,
+    node_id: &C::NodeId,
+    signer: &S,
+    epoch_id: &C::EpochId,
+    epoch_hash: &C::EpochHash,
+) -> Option<VoteSign<C::Signature>>
+where
+    C: Consensus,
+    C::EpochId: Codec,
+    C::EpochHash: Codec,
+    S: Signer<C>,
+{
+    let idx = voters?.index_of(node_id)?;
+    let sign = signer.sign(&vote_message(epoch_id, epoch_hash));
+
+    Some(VoteSign { idx, sign })
+}
+
+fn packet_message<C>(node_id: &C::NodeId, pkt: &[u8]) -> Vec<u8>
+where
+    C: Consensus,
+    C::NodeId: Codec,
+{
+    let mut out = Vec::from(&b"consensus-rs/packet"[..]);
+    node_id.encode(&mut out);
+    out.extend_from_slice(pkt);
+    out
+}
+
+struct Shared<C: Consensus, S> {
+    node_id: C::NodeId,
+    signer: S,
+    voters: spin::Mutex<Option<Voters<C>>>,
+}
+
+impl<C, S> Shared<C, S>
+where
+    C: Consensus,
+    C::NodeId: Codec,
+    C::EpochId: Codec,
+    C::EpochHash: Codec,
+    C::Signature: Codec,
+    S: Signer<C>,
+{
+    /// Fill vote signature of this node to packet, then sign message.
+    fn encode(&self, mut pkt: ConsensusPacket<C>) -> Vec<u8> {
+        let voters = self.voters.lock();
+        let vote_sign = |epoch_id, epoch_hash| {
+            vote_sign(
+                voters.as_ref(),
+                &self.node_id,
+                &self.signer,
+                epoch_id,
+                epoch_hash,
+            )
+        };
+
+        match &mut pkt {
+            Packet::BroadcastPropose(p) => p.vote_sign = vote_sign(&p.epoch_id, &p.epoch_hash),
+            Packet::ResponsePropose(p) => p.vote_sign = vote_sign(&p.epoch_id, &p.epoch_hash),
+            Packet::RoundChange(p) => {
+                p.vote_sign = voters.as_ref().and_then(|voters| {
+                    let idx = voters.index_of(&self.node_id)?;
+                    let msg = round_message(&p.epoch_id, &p.epoch_hash, p.round);
+                    Some(VoteSign {
+                        idx,
+                        sign: self.signer.sign(&msg),
+                    })
+                })
+            }
+            Packet::BroadcastCommit(_) | Packet::NewRound(_) | Packet::Heartbeat(_) => {}
+        }
+
+        drop(voters);
+
+        let pkt = pkt.to_bytes();
+        let sign = self.signer.sign(&packet_message::<C>(&self.node_id, &pkt));
+
+        let mut out = Vec::new();
+        self.node_id.encode(&mut out);
+        pkt.encode(&mut out);
+        sign.encode(&mut out);
+        out
+    }
+
+    fn verify_vote(&self, voters: &Voters<C>, pkt: &ConsensusPacket<C>) -> bool {
+        let verify_msg = |sign: &VoteSign<C::Signature>, msg: &[u8]| {
+            voters
+                .get(sign.idx)
+                .is_some_and(|voter| self.signer.verify(&voter.public_key, msg, &sign.sign))
+        };
+        let verify = |sign: &VoteSign<C::Signature>, epoch_id, epoch_hash| {
+            verify_msg(sign, &vote_message(epoch_id, epoch_hash))
+        };
+
+        match pkt {
+            Packet::BroadcastPropose(p) => p
+                .vote_sign
+                .as_ref()
+                .is_none_or(|s| verify(s, &p.epoch_id, &p.epoch_hash)),
+            Packet::ResponsePropose(p) => p
+                .vote_sign
+                .as_ref()
+                .is_none_or(|s| verify(s, &p.epoch_id, &p.epoch_hash)),
+            Packet::BroadcastCommit(p) => p
+                .vote_signs
+                .iter()
+                .all(|s| verify(s, &p.epoch_id, &p.epoch_hash)),
+            Packet::RoundChange(p) => p
+                .vote_sign
+                .as_ref()
+                .is_none_or(|s| verify_msg(s, &round_message(&p.epoch_id, &p.epoch_hash, p.round))),
+            Packet::NewRound(p) => {
+                let msg = round_message(&p.epoch_id, &p.epoch_hash, p.round);
+                p.vote_signs.iter().all(|s| verify_msg(s, &msg))
+            }
+            Packet::Heartbeat(_) => true,
+        }
+    }
+
+    fn decode(&self, msg: &[u8]) -> Option<(ConsensusPacket<C>, C::NodeId)> {
+        let mut input = msg;
+        let node_id = C::NodeId::decode(&mut input)?;
+        let pkt = Vec::<u8>::decode(&mut input)?;
+        let sign = C::Signature::from_bytes(input)?;
+
+        let voters = self.voters.lock();
+        let voters = voters.as_ref()?;
+
+        let voter = voters.get(voters.index_of(&node_id)?)?;
+
+        if !self.signer.verify(
+            &voter.public_key,
+            &packet_message::<C>(&node_id, &pkt),
+            &sign,
+        ) {
+            log::warn!("Bad signature of packet from {:?}", node_id);
+            return None;
+        }
+
+        let pkt = ConsensusPacket::<C>::from_bytes(&pkt)?;
+
+        Some((pkt, node_id))
+    }
+}
+
+/// Network signing packets over raw transport.
+///
+/// Outgoing packets are signed, vote signature of this node is filled with index in voter set.
+/// Incoming packets from non-voter or with bad signature are dropped, vote signatures are
+/// verified on demand.
+pub struct SignedNetwork<C: Consensus, T, S> {
+    transport: Arc<T>,
+    shared: Arc<Shared<C, S>>,
+}
+
+impl<C, T, S> SignedNetwork<C, T, S>
+where
+    C: Consensus,
+    T: Transport<C>,
+    S: Signer<C>,
+{
+    /// Build network of node over transport.
+    pub fn new(node_id: C::NodeId, transport: T, signer: S) -> Self {
+        Self {
+            transport: Arc::new(transport),
+            shared: Arc::new(Shared {
+                node_id,
+                signer,
+                voters: spin::Mutex::new(None),
+            }),
+        }
+    }
+
+    /// Underline transport.
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+}
+
+impl<C, T, S> Network<C> for SignedNetwork<C, T, S>
+where
+    C: Consensus + 'static,
+    C::NodeId: Codec,
+    C::EpochId: Codec,
+    C::EpochHash: Codec,
+    C::Signature: Codec,
+    T: Transport<C> + 'static,
+    S: Signer<C> + 'static,
+{
+    type Error = T::Error;
+
+    fn node_id(&self) -> C::NodeId {
+        self.shared.node_id.clone()
+    }
+
+    fn send_unsigned(&self, target: Option<C::NodeId>, pkt: ConsensusPacket<C>) {
+        self.transport.send(target, self.shared.encode(pkt));
+    }
+
+    type RecvFuture =
+        Pin<Box<dyn Future<Output = Result<(ConsensusPacket<C>, C::NodeId), T::Error>>>>;
+
+    fn recv(&self) -> Self::RecvFuture {
+        let transport = self.transport.clone();
+        let shared = self.shared.clone();
+
+        Box::pin(async move {
+            loop {
+                let msg = transport.recv().await?;
+
+                match shared.decode(&msg) {
+                    Some(r) => return Ok(r),
+                    None => log::debug!("Drop invalid message of {} bytes", msg.len()),
+                }
+            }
+        })
+    }
+
+    fn sign_vote(
+        &self,
+        epoch_id: &C::EpochId,
+        epoch_hash: &C::EpochHash,
+    ) -> Option<VoteSign<C::Signature>> {
+        let voters = self.shared.voters.lock();
+        vote_sign(
+            voters.as_ref(),
+            &self.shared.node_id,
+            &self.shared.signer,
+            epoch_id,
+            epoch_hash,
+        )
+    }
+
+    fn verify_votes(&self, pkt: &ConsensusPacket<C>) -> bool {
+        let voters = self.shared.voters.lock();
+
+        voters
+            .as_ref()
+            .is_some_and(|voters| self.shared.verify_vote(voters, pkt))
+    }
+
+    fn update_voters(&mut self, voters: &Voters<C>) {
+        self.transport.update_voters(voters);
+        *self.shared.voters.lock() = Some(voters.clone());
+    }
+}
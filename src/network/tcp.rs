@@ -0,0 +1,618 @@
+//! TCP network based on smol.
+//!
+//! Node dials each voter to send messages, and accepts connections from voters to receive
+//! messages. Each frame is a u32 big-endian length followed by message. Messages are signed and
+//! verified by `SignedNetwork` over `TcpTransport`.
+//!
+//! Handshake is mutual. On accept, node sends a random challenge. Dialer responds its node id, its
+//! own random challenge and signature of acceptor challenge, which is verified with public key in
+//! voter set. Acceptor responds signature of dialer challenge, verified by dialer with public key
+//! of node it dialed. Connection with unknown node, with bad signature or not finishing handshake
+//! in time is closed on either side. Connection of voter removed from voter set is closed.
+
+use core::{future::Future, hash::BuildHasher, pin::Pin, time::Duration};
+
+use alloc::{boxed::Box, sync::Arc, vec::Vec};
+use std::{
+    collections::hash_map::RandomState,
+    io,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, RwLock,
+    },
+    time::SystemTime,
+};
+
+use ::smol::{
+    channel::{self, Receiver, Sender},
+    future::FutureExt,
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    Task, Timer,
+};
+
+use crate::{codec::Codec, Consensus, Signer, Transport, VoterConfig};
+
+use super::SignedNetwork;
+
+const CHALLENGE_LEN: usize = 32;
+
+/// Max length of handshake frame, node id, challenge and signature.
+const MAX_HELLO: usize = 4096;
+
+type Voters<C> =
+    VoterConfig<<C as Consensus>::NodeId, <C as Consensus>::PublicKey, <C as Consensus>::Weight>;
+
+/// Config of TCP network.
+#[derive(Debug, Clone)]
+pub struct TcpConfig {
+    reconnect_min: Duration,
+    reconnect_max: Duration,
+    max_frame: usize,
+    queue_size: usize,
+    handshake_timeout: Duration,
+    max_connections: usize,
+}
+
+impl Default for TcpConfig {
+    /// Reconnect from 100 milliseconds to 10 seconds, frame up to 16 MiB, queue 1024 packets,
+    /// handshake in 5 seconds, accept 256 connections.
+    fn default() -> Self {
+        Self {
+            reconnect_min: Duration::from_millis(100),
+            reconnect_max: Duration::from_secs(10),
+            max_frame: 16 << 20,
+            queue_size: 1024,
+            handshake_timeout: Duration::from_secs(5),
+            max_connections: 256,
+        }
+    }
+}
+
+impl TcpConfig {
+    /// Set delay of reconnect.
+    ///
+    /// Delay starts from `min`, doubled on each failure up to `max`.
+    pub fn with_reconnect(mut self, min: Duration, max: Duration) -> Self {
+        self.reconnect_min = min;
+        self.reconnect_max = max;
+        self
+    }
+
+    /// Set max length of frame, larger frame closes connection.
+    pub fn with_max_frame(mut self, max_frame: usize) -> Self {
+        self.max_frame = max_frame;
+        self
+    }
+
+    /// Set max messages queued for each peer and for receive.
+    ///
+    /// Outgoing message is dropped when queue is full, receive waits.
+    pub fn with_queue_size(mut self, queue_size: usize) -> Self {
+        self.queue_size = queue_size;
+        self
+    }
+
+    /// Set time to finish handshake, connection is closed after it.
+    pub fn with_handshake_timeout(mut self, handshake_timeout: Duration) -> Self {
+        self.handshake_timeout = handshake_timeout;
+        self
+    }
+
+    /// Set max accepted connections, new connection over it is closed.
+    pub fn with_max_connections(mut self, max_connections: usize) -> Self {
+        self.max_connections = max_connections;
+        self
+    }
+}
+
+struct Shared<C: Consensus, S> {
+    node_id: C::NodeId,
+    signer: S,
+    voters: RwLock<Option<Voters<C>>>,
+    config: TcpConfig,
+    /// Accepted connections, dropping sender closes connection.
+    connections: Mutex<Vec<(C::NodeId, Sender<()>)>>,
+}
+
+struct Peer<C: Consensus> {
+    node_id: C::NodeId,
+    sender: Sender<Vec<u8>>,
+    _task: Task<()>,
+}
+
+/// Error of TCP network.
+#[derive(Debug)]
+pub struct NetworkClosed;
+
+/// Transport over TCP.
+///
+/// `addresses` is address book of nodes. Voters in it are dialed, and hung up when removed
+/// from voter set. Broadcast message is sent to voters, also to this node.
+///
+/// Observers aren't dialed or accepted, message to a observer is dropped. So commits of
+/// `BroadcastNetwork` don't reach observers over this transport.
+pub struct TcpTransport<C: Consensus, S> {
+    shared: Arc<Shared<C, S>>,
+    addresses: Vec<(C::NodeId, SocketAddr)>,
+    peers: Mutex<Vec<Peer<C>>>,
+    local: Sender<Vec<u8>>,
+    recver: Receiver<Vec<u8>>,
+    local_addr: SocketAddr,
+    _acceptor: Task<()>,
+}
+
+/// Network over TCP.
+pub type TcpNetwork<C, S> = SignedNetwork<C, TcpTransport<C, Arc<S>>, Arc<S>>;
+
+impl<C, S> TcpTransport<C, S>
+where
+    C: Consensus + 'static,
+    C::NodeId: Codec + Send + Sync,
+    C::PublicKey: Send + Sync,
+    C::EpochId: Codec + Send + Sync,
+    C::EpochHash: Codec + Send + Sync,
+    C::Weight: Send + Sync,
+    C::Signature: Codec + Send + Sync,
+    S: Signer<C> + Send + Sync + 'static,
+{
+    /// Listen on `addr`, build network of this node.
+    pub async fn bind(
+        node_id: C::NodeId,
+        addr: SocketAddr,
+        addresses: Vec<(C::NodeId, SocketAddr)>,
+        signer: S,
+        config: TcpConfig,
+    ) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr).await?;
+        let local_addr = listener.local_addr()?;
+
+        let shared = Arc::new(Shared {
+            node_id,
+            signer,
+            voters: RwLock::new(None),
+            connections: Mutex::new(Vec::new()),
+            config,
+        });
+
+        let (local, recver) = channel::bounded(shared.config.queue_size);
+
+        let acceptor = ::smol::spawn(accept(shared.clone(), listener, local.clone()));
+
+        Ok(Self {
+            shared,
+            addresses,
+            peers: Mutex::new(Vec::new()),
+            local,
+            recver,
+            local_addr,
+            _acceptor: acceptor,
+        })
+    }
+
+    /// Address this node listening on.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+}
+
+impl<C, S> TcpNetwork<C, S>
+where
+    C: Consensus + 'static,
+    C::NodeId: Codec + Send + Sync,
+    C::PublicKey: Send + Sync,
+    C::EpochId: Codec + Send + Sync,
+    C::EpochHash: Codec + Send + Sync,
+    C::Weight: Send + Sync,
+    C::Signature: Codec + Send + Sync,
+    S: Signer<C> + Send + Sync + 'static,
+{
+    /// Listen on `addr`, build network of this node.
+    ///
+    /// Signer is shared by handshake of transport and signing of packets.
+    pub async fn bind(
+        node_id: C::NodeId,
+        addr: SocketAddr,
+        addresses: Vec<(C::NodeId, SocketAddr)>,
+        signer: S,
+        config: TcpConfig,
+    ) -> io::Result<Self> {
+        let signer = Arc::new(signer);
+        let transport =
+            TcpTransport::bind(node_id.clone(), addr, addresses, signer.clone(), config).await?;
+
+        Ok(SignedNetwork::new(node_id, transport, signer))
+    }
+}
+
+impl<C, S> Transport<C> for TcpTransport<C, S>
+where
+    C: Consensus + 'static,
+    C::NodeId: Codec + Send + Sync,
+    C::PublicKey: Send + Sync,
+    C::EpochId: Codec + Send + Sync,
+    C::EpochHash: Codec + Send + Sync,
+    C::Weight: Send + Sync,
+    C::Signature: Codec + Send + Sync,
+    S: Signer<C> + Send + Sync + 'static,
+{
+    type Error = NetworkClosed;
+
+    fn send(&self, target: Option<C::NodeId>, msg: Vec<u8>) {
+        let voters = self.shared.voters.read().unwrap_or_else(|e| e.into_inner());
+        let peers = self.peers.lock().unwrap_or_else(|e| e.into_inner());
+
+        for peer in peers.iter() {
+            let is_target = match &target {
+                Some(t) => t == &peer.node_id,
+                None => voters.as_ref().is_some_and(|v| v.contains(&peer.node_id)),
+            };
+
+            if is_target {
+                if let Err(e) = peer.sender.try_send(msg.clone()) {
+                    log::debug!("Drop message to {:?}: {:?}", peer.node_id, e);
+                }
+            }
+        }
+
+        drop(peers);
+        drop(voters);
+
+        if target.as_ref().is_none_or(|t| t == &self.shared.node_id) {
+            let _ = self.local.try_send(msg);
+        }
+    }
+
+    type RecvFuture =
+        Pin<Box<dyn Future<Output = core::result::Result<Vec<u8>, NetworkClosed>> + Send>>;
+
+    fn recv(&self) -> Self::RecvFuture {
+        let recver = self.recver.clone();
+
+        Box::pin(async move { recver.recv().await.map_err(|_| NetworkClosed) })
+    }
+
+    fn update_voters(&self, voters: &Voters<C>) {
+        let shared = &self.shared;
+
+        *shared.voters.write().unwrap_or_else(|e| e.into_inner()) = Some(voters.clone());
+
+        // Hang up removed voters.
+        shared
+            .connections
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|(node_id, _)| voters.contains(node_id));
+
+        let mut peers = self.peers.lock().unwrap_or_else(|e| e.into_inner());
+        peers.retain(|p| voters.contains(&p.node_id));
+
+        for voter in voters.voters() {
+            let node_id = &voter.voter_id;
+
+            if node_id == &shared.node_id || peers.iter().any(|p| &p.node_id == node_id) {
+                continue;
+            }
+
+            let addr = match self.addresses.iter().find(|(n, _)| n == node_id) {
+                Some((_, addr)) => *addr,
+                None => {
+                    log::warn!("No address of voter {:?}", node_id);
+                    continue;
+                }
+            };
+
+            let (sender, recver) = channel::bounded(shared.config.queue_size);
+            let task = ::smol::spawn(dial(shared.clone(), node_id.clone(), addr, recver));
+
+            peers.push(Peer {
+                node_id: node_id.clone(),
+                sender,
+                _task: task,
+            });
+        }
+    }
+}
+
+/// Message signed in handshake, `side` tells dialer from acceptor so signature can't be reflected.
+fn handshake_message<C>(
+    side: &[u8],
+    challenge: &[u8],
+    acceptor: &C::NodeId,
+    dialer: &C::NodeId,
+) -> Vec<u8>
+where
+    C: Consensus,
+    C::NodeId: Codec,
+{
+    let mut out = Vec::from(&b"consensus-rs/tcp/"[..]);
+    out.extend_from_slice(side);
+    out.extend_from_slice(challenge);
+    acceptor.encode(&mut out);
+    dialer.encode(&mut out);
+    out
+}
+
+fn challenge() -> [u8; CHALLENGE_LEN] {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let time = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+
+    // RandomState is seeded randomly by std.
+    let state = RandomState::new();
+    let mut out = [0u8; CHALLENGE_LEN];
+
+    for (i, chunk) in out.chunks_mut(8).enumerate() {
+        let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let h = state.hash_one((time, n, i));
+        chunk.copy_from_slice(&h.to_be_bytes());
+    }
+
+    out
+}
+
+async fn write_frame(stream: &mut TcpStream, frame: &[u8]) -> io::Result<()> {
+    let mut buf = Vec::with_capacity(frame.len() + 4);
+    buf.extend_from_slice(&(frame.len() as u32).to_be_bytes());
+    buf.extend_from_slice(frame);
+
+    stream.write_all(&buf).await
+}
+
+async fn read_frame(stream: &mut TcpStream, max_frame: usize) -> io::Result<Vec<u8>> {
+    let mut len = [0u8; 4];
+    stream.read_exact(&mut len).await?;
+
+    let len = u32::from_be_bytes(len) as usize;
+    if len > max_frame {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "frame too large",
+        ));
+    }
+
+    let mut frame = alloc::vec![0u8; len];
+    stream.read_exact(&mut frame).await?;
+
+    Ok(frame)
+}
+
+async fn with_timeout<T>(
+    timeout: Duration,
+    fut: impl Future<Output = io::Result<T>>,
+) -> io::Result<T> {
+    let timer = async {
+        Timer::after(timeout).await;
+        Err(io::Error::new(io::ErrorKind::TimedOut, "handshake timeout"))
+    };
+
+    fut.or(timer).await
+}
+
+async fn dial<C, S>(
+    shared: Arc<Shared<C, S>>,
+    target: C::NodeId,
+    addr: SocketAddr,
+    outbound: Receiver<Vec<u8>>,
+) where
+    C: Consensus,
+    C::NodeId: Codec,
+    C::Signature: Codec,
+    S: Signer<C>,
+{
+    let mut delay = shared.config.reconnect_min;
+
+    loop {
+        match connect(&shared, &target, addr, &outbound, &mut delay).await {
+            // Peer is removed.
+            Ok(()) => return,
+            Err(e) => log::debug!("Connection to {:?} at {} lost: {}", target, addr, e),
+        }
+
+        Timer::after(delay).await;
+        delay = (delay * 2).min(shared.config.reconnect_max);
+    }
+}
+
+async fn connect<C, S>(
+    shared: &Shared<C, S>,
+    target: &C::NodeId,
+    addr: SocketAddr,
+    outbound: &Receiver<Vec<u8>>,
+    delay: &mut Duration,
+) -> io::Result<()>
+where
+    C: Consensus,
+    C::NodeId: Codec,
+    C::Signature: Codec,
+    S: Signer<C>,
+{
+    let invalid = |msg| io::Error::new(io::ErrorKind::InvalidData, msg);
+
+    let mut stream = TcpStream::connect(addr).await?;
+    stream.set_nodelay(true)?;
+
+    let own_challenge = challenge();
+
+    let handshake = async {
+        let challenge = read_frame(&mut stream, CHALLENGE_LEN).await?;
+
+        let sign = shared.signer.sign(&handshake_message::<C>(
+            b"dial",
+            &challenge,
+            target,
+            &shared.node_id,
+        ));
+
+        let mut hello = Vec::new();
+        shared.node_id.encode(&mut hello);
+        hello.extend_from_slice(&own_challenge);
+        sign.encode(&mut hello);
+        write_frame(&mut stream, &hello).await?;
+
+        read_frame(&mut stream, MAX_HELLO).await
+    };
+    let reply = with_timeout(shared.config.handshake_timeout, handshake).await?;
+
+    let sign = C::Signature::from_bytes(&reply).ok_or_else(|| invalid("bad reply"))?;
+
+    let authenticated = {
+        let voters = shared.voters.read().unwrap_or_else(|e| e.into_inner());
+
+        voters
+            .as_ref()
+            .and_then(|v| v.get(v.index_of(target)?))
+            .is_some_and(|voter| {
+                let msg =
+                    handshake_message::<C>(b"accept", &own_challenge, target, &shared.node_id);
+                shared.signer.verify(&voter.public_key, &msg, &sign)
+            })
+    };
+
+    if !authenticated {
+        log::warn!("Reject connection to {:?} at {}", target, addr);
+        return Err(invalid("authentication failed"));
+    }
+
+    log::debug!("Connected to {:?} at {}", target, addr);
+    *delay = shared.config.reconnect_min;
+
+    while let Ok(frame) = outbound.recv().await {
+        write_frame(&mut stream, &frame).await?;
+    }
+
+    Ok(())
+}
+
+async fn accept<C, S>(shared: Arc<Shared<C, S>>, listener: TcpListener, inbound: Sender<Vec<u8>>)
+where
+    C: Consensus + 'static,
+    C::NodeId: Codec + Send + Sync,
+    C::PublicKey: Send + Sync,
+    C::Weight: Send + Sync,
+    C::Signature: Codec + Send + Sync,
+    S: Signer<C> + Send + Sync + 'static,
+{
+    // Dropping acceptor stops all connections.
+    let mut tasks: Vec<Task<()>> = Vec::new();
+
+    loop {
+        match listener.accept().await {
+            Ok((stream, addr)) => {
+                tasks.retain(|t| !t.is_finished());
+
+                if tasks.len() >= shared.config.max_connections {
+                    log::warn!("Too many connections, close connection from {}", addr);
+                    continue;
+                }
+
+                let shared = shared.clone();
+                let inbound = inbound.clone();
+
+                tasks.push(::smol::spawn(async move {
+                    if let Err(e) = serve(&shared, stream, &inbound).await {
+                        log::debug!("Connection from {} closed: {}", addr, e);
+                    }
+                }));
+            }
+            Err(e) => {
+                log::warn!("Accept failed: {}", e);
+                Timer::after(shared.config.reconnect_min).await;
+            }
+        }
+    }
+}
+
+async fn serve<C, S>(
+    shared: &Shared<C, S>,
+    mut stream: TcpStream,
+    inbound: &Sender<Vec<u8>>,
+) -> io::Result<()>
+where
+    C: Consensus,
+    C::NodeId: Codec,
+    C::Signature: Codec,
+    S: Signer<C>,
+{
+    let invalid = |msg| io::Error::new(io::ErrorKind::InvalidData, msg);
+
+    stream.set_nodelay(true)?;
+
+    let challenge = challenge();
+
+    let handshake = async {
+        write_frame(&mut stream, &challenge).await?;
+        read_frame(&mut stream, MAX_HELLO).await
+    };
+    let hello = with_timeout(shared.config.handshake_timeout, handshake).await?;
+
+    let mut input = &hello[..];
+    let node_id = C::NodeId::decode(&mut input).ok_or_else(|| invalid("bad hello"))?;
+    if input.len() < CHALLENGE_LEN {
+        return Err(invalid("bad hello"));
+    }
+    let (dialer_challenge, input) = input.split_at(CHALLENGE_LEN);
+    let sign = C::Signature::from_bytes(input).ok_or_else(|| invalid("bad hello"))?;
+
+    let authenticated = {
+        let voters = shared.voters.read().unwrap_or_else(|e| e.into_inner());
+
+        voters
+            .as_ref()
+            .and_then(|v| v.get(v.index_of(&node_id)?))
+            .is_some_and(|voter| {
+                let msg = handshake_message::<C>(b"dial", &challenge, &shared.node_id, &node_id);
+                shared.signer.verify(&voter.public_key, &msg, &sign)
+            })
+    };
+
+    if !authenticated {
+        log::warn!("Reject connection from {:?}", node_id);
+        return Err(invalid("authentication failed"));
+    }
+
+    // Prove identity of this node to dialer.
+    let sign = shared.signer.sign(&handshake_message::<C>(
+        b"accept",
+        dialer_challenge,
+        &shared.node_id,
+        &node_id,
+    ));
+    let mut reply = Vec::new();
+    sign.encode(&mut reply);
+    with_timeout(
+        shared.config.handshake_timeout,
+        write_frame(&mut stream, &reply),
+    )
+    .await?;
+
+    log::debug!("Accepted connection from {:?}", node_id);
+
+    let (closer, closed) = channel::bounded(1);
+    {
+        let mut connections = shared.connections.lock().unwrap_or_else(|e| e.into_inner());
+        // Forget finished connections.
+        connections.retain(|(_, c)| !c.is_closed());
+        connections.push((node_id, closer));
+    }
+
+    loop {
+        let frame = read_frame(&mut stream, shared.config.max_frame);
+        let closed = async {
+            let _ = closed.recv().await;
+            Err(invalid("voter removed"))
+        };
+
+        let frame = frame.or(closed).await?;
+
+        // Message is verified by `SignedNetwork`.
+        if inbound.send(frame).await.is_err() {
+            // Transport dropped.
+            return Ok(());
+        }
+    }
+}
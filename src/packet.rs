@@ -5,35 +5,89 @@ use alloc::vec::Vec;
 use crate::{EpochHash, EpochId, Signature, VoteSign};
 
 /// Broadcast propopse to other node
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct BroadcastPropose<I: EpochId, H: EpochHash, S: Signature> {
     pub epoch_id: I,
     pub epoch_hash: H,
+    /// Round of proposal after latest committed epoch of proposer.
+    pub round: u64,
     pub vote_sign: Option<VoteSign<S>>,
 }
 
 /// Response propopse to proposer
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ResponsePropose<I: EpochId, H: EpochHash, S: Signature> {
     pub epoch_id: I,
     pub epoch_hash: H,
+    /// Round of voted proposal.
+    pub round: u64,
     pub vote_sign: Option<VoteSign<S>>,
 }
 
+/// Proposal voted on a round
+///
+/// Voter is locked on it across rounds, proposer of a certified round proposes the highest one
+/// again.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Voted<I: EpochId, H: EpochHash> {
+    pub epoch_id: I,
+    pub epoch_hash: H,
+    pub round: u64,
+}
+
 /// Broadcast commit to other node
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct BroadcastCommit<I: EpochId, H: EpochHash, S: Signature> {
     pub epoch_id: I,
     pub epoch_hash: H,
     pub vote_signs: Vec<VoteSign<S>>,
 }
 
+/// Vote to leave round of epoch after latest committed epoch
+///
+/// `epoch_id` and `epoch_hash` are of latest committed epoch of sender.
+#[derive(Debug, Clone)]
+pub struct RoundChange<I: EpochId, H: EpochHash, S: Signature> {
+    pub epoch_id: I,
+    pub epoch_hash: H,
+    pub round: u64,
+    /// Proposal sender is locked on, of next epoch.
+    pub voted: Option<Voted<I, H>>,
+    pub vote_sign: Option<VoteSign<S>>,
+}
+
+/// Round change votes of a quorum, as certificate of new round
+#[derive(Debug, Clone)]
+pub struct NewRound<I: EpochId, H: EpochHash, S: Signature> {
+    pub epoch_id: I,
+    pub epoch_hash: H,
+    pub round: u64,
+    /// Highest voted proposal in round change votes, proposed again on new round.
+    pub voted: Option<Voted<I, H>>,
+    pub vote_signs: Vec<VoteSign<S>>,
+}
+
+/// Heartbeat of stable leader
+///
+/// `epoch_id` and `epoch_hash` are of latest committed epoch of leader.
+#[derive(Debug, Clone)]
+pub struct Heartbeat<I: EpochId, H: EpochHash> {
+    pub epoch_id: I,
+    pub epoch_hash: H,
+    pub round: u64,
+    /// Increased on each heartbeat of leader, so repeated heartbeats aren't same frame.
+    pub seq: u64,
+}
+
 /// Packet for network
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Packet<I: EpochId, H: EpochHash, S: Signature> {
     BroadcastPropose(BroadcastPropose<I, H, S>),
     ResponsePropose(ResponsePropose<I, H, S>),
     BroadcastCommit(BroadcastCommit<I, H, S>),
+    RoundChange(RoundChange<I, H, S>),
+    NewRound(NewRound<I, H, S>),
+    Heartbeat(Heartbeat<I, H>),
 }
 
 impl<I: EpochId, H: EpochHash, S: Signature> Packet<I, H, S> {
@@ -45,18 +99,32 @@ impl<I: EpochId, H: EpochHash, S: Signature> Packet<I, H, S> {
         matches!(self, Packet::ResponsePropose(_))
     }
 
-    pub fn response_propose_from_id_hash(epoch_id: I, epoch_hash: H) -> Self {
+    /// Name of packet variant.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Packet::BroadcastPropose(_) => "broadcast_propose",
+            Packet::ResponsePropose(_) => "response_propose",
+            Packet::BroadcastCommit(_) => "broadcast_commit",
+            Packet::RoundChange(_) => "round_change",
+            Packet::NewRound(_) => "new_round",
+            Packet::Heartbeat(_) => "heartbeat",
+        }
+    }
+
+    pub fn response_propose_from_id_hash(epoch_id: I, epoch_hash: H, round: u64) -> Self {
         Self::ResponsePropose(ResponsePropose {
             epoch_id,
             epoch_hash,
+            round,
             vote_sign: None,
         })
     }
 
-    pub fn broadcast_propose_from_id_hash(epoch_id: I, epoch_hash: H) -> Self {
+    pub fn broadcast_propose_from_id_hash(epoch_id: I, epoch_hash: H, round: u64) -> Self {
         Self::BroadcastPropose(BroadcastPropose {
             epoch_hash,
             epoch_id,
+            round,
             vote_sign: None,
         })
     }
@@ -72,4 +140,44 @@ impl<I: EpochId, H: EpochHash, S: Signature> Packet<I, H, S> {
             vote_signs,
         })
     }
+
+    pub fn round_change_from_id_hash(
+        epoch_id: I,
+        epoch_hash: H,
+        round: u64,
+        voted: Option<Voted<I, H>>,
+    ) -> Self {
+        Self::RoundChange(RoundChange {
+            epoch_id,
+            epoch_hash,
+            round,
+            voted,
+            vote_sign: None,
+        })
+    }
+
+    pub fn heartbeat_from_id_hash(epoch_id: I, epoch_hash: H, round: u64, seq: u64) -> Self {
+        Self::Heartbeat(Heartbeat {
+            epoch_id,
+            epoch_hash,
+            round,
+            seq,
+        })
+    }
+
+    pub fn new_round_from_id_hash(
+        epoch_id: I,
+        epoch_hash: H,
+        round: u64,
+        voted: Option<Voted<I, H>>,
+        vote_signs: Vec<VoteSign<S>>,
+    ) -> Self {
+        Self::NewRound(NewRound {
+            epoch_id,
+            epoch_hash,
+            round,
+            voted,
+            vote_signs,
+        })
+    }
 }
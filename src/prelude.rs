@@ -5,12 +5,16 @@ use core::{
     future::Future,
     iter::Sum,
     ops::{Add, AddAssign, Mul},
+    time::Duration,
 };
 
-use alloc::vec::Vec;
+use alloc::{sync::Arc, vec::Vec};
 use num_traits::{One, Zero};
 
-use crate::{packet::Packet, Role, Voter};
+use crate::{
+    inbound::InboundPolicy, packet::Packet, quorum::FaultTolerance, timeout::TimeoutPolicy, Role,
+    VoteSign, Voter, VoterConfig,
+};
 
 /// EpochId type.
 ///
@@ -80,25 +84,87 @@ pub trait Consensus {
     >;
     /// Got latest voter set.
     ///
-    /// This method only call on node startup.
+    /// This method is called on node startup, and on commit after `ReloadVoterSet` command.
     fn latest_voter_set(&self) -> Self::LatestVoterSetFuture;
 
-    /// Future of `step_timer`.
+    /// Future of `timer`.
     type Timer: Future<Output = ()>;
-    /// Build a timer for step.
-    fn step_timer(&self, role: &Role, step: u8) -> Self::Timer;
+    /// Build a timer fired after `timeout`.
+    ///
+    /// Breaking change: it replaces `step_timer` as the method to implement, timeout of step is
+    /// computed by engine from `timeout_policy`.
+    fn timer(&self, timeout: Duration) -> Self::Timer;
+
+    /// Build a timer for step on round 0.
+    ///
+    /// Not called by engine, kept for callers of the old API.
+    #[deprecated(note = "implement `timer` and `timeout_policy` instead")]
+    fn step_timer(&self, role: &Role, step: u8) -> Self::Timer {
+        self.timer(self.timeout_policy().timeout(role, step, 0, 0))
+    }
+
+    /// Timeout policy of step timer.
+    ///
+    /// This method only call on node startup.
+    fn timeout_policy(&self) -> TimeoutPolicy {
+        TimeoutPolicy::default()
+    }
+
+    /// Policy of inbound packets.
+    ///
+    /// This method only call on node startup.
+    fn inbound_policy(&self) -> InboundPolicy {
+        InboundPolicy::default()
+    }
 
     /// Future of compute_proposer
     type ComputeProposerFuture: Future<Output = Self::NodeId>;
     /// Compute proposer based on epoch hash.
     fn compute_proposer(&self, epoch_hash: &Self::EpochHash) -> Self::ComputeProposerFuture;
 
+    /// Fault tolerance type of consensus.
+    ///
+    /// Decide weight of quorum.
+    fn fault_tolerance(&self) -> FaultTolerance {
+        FaultTolerance::Cft
+    }
+
+    /// Use joint consensus on voter set change.
+    ///
+    /// If true, the epoch after a voter set change is a transition epoch, which needs majority
+    /// in both old and new voter set.
+    fn joint_consensus(&self) -> bool {
+        false
+    }
+
+    /// Propose next epoch once previous epoch reached quorum, before it's committed.
+    ///
+    /// Followers vote on two epochs in flight at most, commits are applied in order.
+    fn pipelined(&self) -> bool {
+        false
+    }
+
+    /// Keep proposer leading across epochs, instead of computing it from each epoch hash.
+    ///
+    /// Idle proposer sends heartbeats. A new proposer is only elected when a round change is
+    /// certified by a quorum, rotating to next voter of the voter set.
+    fn stable_leader(&self) -> bool {
+        false
+    }
+
+    /// Current time, as duration since unix epoch.
+    ///
+    /// Only used to report status. Return `None` if no clock.
+    fn now(&self) -> Option<Duration> {
+        None
+    }
+
     // TODO: Add EpochHash unique check.
 }
 /// Network for node.
 pub trait Network<C: Consensus> {
     /// Error for underline network.
-    type Error: Debug + 'static;
+    type Error: Debug + Send + Sync + 'static;
 
     /// Get current node key.
     fn node_id(&self) -> C::NodeId;
@@ -116,12 +182,81 @@ pub trait Network<C: Consensus> {
     >;
     /// Receive packet from network.
     fn recv(&self) -> Self::RecvFuture;
+
+    /// Sign vote of this node on epoch.
+    ///
+    /// Proposer counts its own vote with it, without waiting broadcast looped back.
+    /// Return `None` if not supported or node isn't voter.
+    fn sign_vote(
+        &self,
+        _epoch_id: &C::EpochId,
+        _epoch_hash: &C::EpochHash,
+    ) -> Option<VoteSign<C::Signature>> {
+        None
+    }
+
+    /// Verify vote signatures in packet.
+    ///
+    /// Engine only calls it on packet passed inbound checks, so dropped packets don't cost
+    /// signature verification. Network which verified them on receive keeps default.
+    fn verify_votes(&self, _pkt: &Packet<C::EpochId, C::EpochHash, C::Signature>) -> bool {
+        true
+    }
+
+    /// Hook for voter set
+    ///
+    /// Called on node startup and when voter set changed. Network can connect to voters here.
+    fn update_voters(&mut self, _voters: &VoterConfig<C::NodeId, C::PublicKey, C::Weight>) {}
+}
+
+/// Raw transport for node.
+///
+/// Only send and receive bytes, signature and sender are handled by `network::SignedNetwork`.
+pub trait Transport<C: Consensus> {
+    /// Error for underline transport.
+    type Error: Debug + Send + Sync + 'static;
+
+    /// Send bytes to other node.
+    ///
+    /// If `target` is `None`, broadcast to all nodes, include this node.
+    fn send(&self, target: Option<C::NodeId>, msg: Vec<u8>);
+
+    /// Future for recv method.
+    type RecvFuture: Future<Output = Result<Vec<u8>, Self::Error>>;
+    /// Receive bytes from network.
+    fn recv(&self) -> Self::RecvFuture;
+
+    /// Hook for voter set
+    ///
+    /// Called on node startup and when voter set changed.
+    fn update_voters(&self, _voters: &VoterConfig<C::NodeId, C::PublicKey, C::Weight>) {}
+}
+
+/// Signer of node.
+///
+/// Sign message with secret key of this node, verify message of other nodes.
+pub trait Signer<C: Consensus> {
+    /// Sign message.
+    fn sign(&self, msg: &[u8]) -> C::Signature;
+
+    /// Verify signature of message with public key.
+    fn verify(&self, public_key: &C::PublicKey, msg: &[u8], sign: &C::Signature) -> bool;
+}
+
+impl<C: Consensus, S: Signer<C> + ?Sized> Signer<C> for Arc<S> {
+    fn sign(&self, msg: &[u8]) -> C::Signature {
+        (**self).sign(msg)
+    }
+
+    fn verify(&self, public_key: &C::PublicKey, msg: &[u8], sign: &C::Signature) -> bool {
+        (**self).verify(public_key, msg, sign)
+    }
 }
 
 /// Application.
 pub trait App<C: Consensus> {
     /// Application Error
-    type Error: Debug + 'static;
+    type Error: Debug + Send + Sync + 'static;
 
     /// Future for propose_epoch method.
     type ProposeEpochFuture: Future<Output = Result<(C::EpochId, C::EpochHash), Self::Error>>;
@@ -130,6 +265,9 @@ pub trait App<C: Consensus> {
     ///
     /// When node propose a epoch, call this function.
     /// Only called by proposer.
+    ///
+    /// If `Consensus::pipelined`, it may be called before previous proposed epoch is committed,
+    /// new epoch should follow the last proposed epoch.
     fn propose_epoch(&mut self) -> Self::ProposeEpochFuture;
 
     /// Future for enter_step
@@ -152,4 +290,11 @@ pub trait App<C: Consensus> {
     ///
     /// Means all voter confirm this epoch.
     fn commit(&mut self, epoch_id: &C::EpochId, epoch_hash: &C::EpochHash) -> Self::CommitFuture;
+
+    /// Future for shutdown
+    type ShutdownFuture: Future<Output = Result<(), Self::Error>>;
+    /// Shutdown hook
+    ///
+    /// Called when engine stopped by `run`. Flush any persisted state here.
+    fn shutdown(&mut self) -> Self::ShutdownFuture;
 }
@@ -0,0 +1,184 @@
+//! Quorum check for CFT and BFT.
+
+use alloc::vec::Vec;
+
+use crate::{EpochHash, EpochId, NodeId, VoterConfig, VoterSet, Weight};
+
+/// Fault tolerance type
+///
+/// - $n$: Total weight of all the voters.
+/// - $t$: The weight of fault voters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FaultTolerance {
+    /// Crash Fault Tolerance. $2t < n$, quorum is weight more than $n / 2$.
+    #[default]
+    Cft,
+    /// Byzantine Fault Tolerance. $3t < n$, quorum is weight more than $2n / 3$.
+    Bft,
+}
+
+impl FaultTolerance {
+    /// Check weight reach quorum of total weight.
+    pub fn is_quorum<W: Weight>(&self, weight: &W, total_weight: &W) -> bool {
+        let one: W = num_traits::one();
+        let two: W = one.clone() + one.clone();
+
+        match self {
+            Self::Cft => weight.clone() * two > total_weight.clone(),
+            Self::Bft => {
+                let three = two.clone() + one;
+                weight.clone() * three > total_weight.clone() * two
+            }
+        }
+    }
+
+    /// Check voters of index reach quorum in voter set.
+    ///
+    /// Same index only count once.
+    pub fn has_quorum<V, P, W>(&self, voter_set: &VoterSet<V, P, W>, idxs: &[u64]) -> bool
+    where
+        V: NodeId,
+        W: Weight,
+    {
+        let weight = voter_set
+            .voters()
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| idxs.contains(&(*i as u64)))
+            .map(|(_, v)| v.weight.clone())
+            .sum();
+
+        self.is_quorum(&weight, voter_set.total_weight())
+    }
+}
+
+/// Votes on a (epoch, round, hash).
+#[derive(Debug, Clone)]
+pub struct Votes<I, H> {
+    pub epoch_id: I,
+    pub round: u64,
+    pub epoch_hash: H,
+    voters: Vec<u64>,
+}
+
+impl<I, H> Votes<I, H> {
+    /// Index of voters, in order of vote.
+    pub fn voters(&self) -> &[u64] {
+        &self.voters
+    }
+}
+
+/// Collect votes and check quorum.
+///
+/// Votes are grouped by (epoch, round, hash), each voter only count once in a group.
+#[derive(Debug, Clone)]
+pub struct QuorumCollector<I, H> {
+    fault_tolerance: FaultTolerance,
+    votes: Vec<Votes<I, H>>,
+}
+
+impl<I, H> QuorumCollector<I, H>
+where
+    I: EpochId,
+    H: EpochHash,
+{
+    /// Build collector with fault tolerance type.
+    pub fn new(fault_tolerance: FaultTolerance) -> Self {
+        Self {
+            fault_tolerance,
+            votes: Vec::new(),
+        }
+    }
+
+    /// Fault tolerance type.
+    pub fn fault_tolerance(&self) -> FaultTolerance {
+        self.fault_tolerance
+    }
+
+    /// Add vote of voter index.
+    ///
+    /// Return false if this voter already voted.
+    pub fn add_vote(&mut self, epoch_id: &I, round: u64, epoch_hash: &H, idx: u64) -> bool {
+        match self
+            .votes
+            .iter_mut()
+            .find(|v| &v.epoch_id == epoch_id && v.round == round && &v.epoch_hash == epoch_hash)
+        {
+            Some(votes) if votes.voters.contains(&idx) => false,
+            Some(votes) => {
+                votes.voters.push(idx);
+                true
+            }
+            None => {
+                self.votes.push(Votes {
+                    epoch_id: epoch_id.clone(),
+                    round,
+                    epoch_hash: epoch_hash.clone(),
+                    voters: alloc::vec![idx],
+                });
+                true
+            }
+        }
+    }
+
+    /// Get votes of (epoch, round, hash).
+    pub fn votes(&self, epoch_id: &I, round: u64, epoch_hash: &H) -> Option<&Votes<I, H>> {
+        self.votes
+            .iter()
+            .find(|v| &v.epoch_id == epoch_id && v.round == round && &v.epoch_hash == epoch_hash)
+    }
+
+    /// Accumulated weight of (epoch, round, hash).
+    ///
+    /// On joint voter config, it's the weight of all voters in both voter sets.
+    pub fn weight<V, P, W>(
+        &self,
+        epoch_id: &I,
+        round: u64,
+        epoch_hash: &H,
+        config: &VoterConfig<V, P, W>,
+    ) -> W
+    where
+        V: NodeId,
+        P: Clone,
+        W: Weight,
+    {
+        self.votes(epoch_id, round, epoch_hash)
+            .map(|v| {
+                v.voters
+                    .iter()
+                    .filter_map(|idx| config.get(*idx))
+                    .map(|v| v.weight.clone())
+                    .sum()
+            })
+            .unwrap_or_else(num_traits::zero)
+    }
+
+    /// Check (epoch, round, hash) reach quorum.
+    pub fn has_quorum<V, P, W>(
+        &self,
+        epoch_id: &I,
+        round: u64,
+        epoch_hash: &H,
+        config: &VoterConfig<V, P, W>,
+    ) -> bool
+    where
+        V: NodeId,
+        P: Clone,
+        W: Weight,
+    {
+        self.votes(epoch_id, round, epoch_hash)
+            .map(|v| config.has_quorum(&v.voters, self.fault_tolerance))
+            .unwrap_or(false)
+    }
+
+    /// Remove votes of epoch not after `epoch_id`.
+    pub fn prune(&mut self, epoch_id: &I) {
+        self.votes.retain(|v| &v.epoch_id > epoch_id);
+    }
+
+    /// Remove all votes.
+    pub fn clear(&mut self) {
+        self.votes.clear();
+    }
+}
@@ -0,0 +1,166 @@
+//! In-process channel network shared by runtime adapters.
+//!
+//! Adapter only provides the channel, signing and receiving is done here.
+
+use core::{future::Future, pin::Pin};
+
+use alloc::{boxed::Box, sync::Arc, vec::Vec};
+use std::sync::Mutex;
+
+use crate::{packet::Packet, Consensus, Network, VoteSign};
+
+use super::VoteSigner;
+
+pub(super) type Envelope<C> = (
+    Packet<<C as Consensus>::EpochId, <C as Consensus>::EpochHash, <C as Consensus>::Signature>,
+    <C as Consensus>::NodeId,
+);
+
+/// Unbounded channel of a runtime.
+pub trait Queue<T> {
+    type Sender;
+    type Receiver;
+
+    /// Build channel.
+    fn unbounded() -> (Self::Sender, Self::Receiver);
+
+    /// Send message, it's dropped if receiver dropped.
+    fn send(sender: &Self::Sender, msg: T);
+
+    /// Receive next message, `None` if all senders dropped.
+    fn recv(receiver: &Self::Receiver) -> Pin<Box<dyn Future<Output = Option<T>> + Send>>;
+}
+
+/// Error of channel network.
+#[derive(Debug)]
+pub struct ChannelClosed;
+
+type Peers<C, Q> = Vec<(<C as Consensus>::NodeId, <Q as Queue<Envelope<C>>>::Sender)>;
+
+/// Hub of in-process nodes.
+///
+/// Cheap to clone, all clones share same nodes.
+pub struct ChannelHub<C: Consensus, Q: Queue<Envelope<C>>> {
+    peers: Arc<Mutex<Peers<C, Q>>>,
+}
+
+impl<C: Consensus, Q: Queue<Envelope<C>>> Clone for ChannelHub<C, Q> {
+    fn clone(&self) -> Self {
+        Self {
+            peers: self.peers.clone(),
+        }
+    }
+}
+
+impl<C: Consensus, Q: Queue<Envelope<C>>> Default for ChannelHub<C, Q> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: Consensus, Q: Queue<Envelope<C>>> ChannelHub<C, Q> {
+    /// Build empty hub.
+    pub fn new() -> Self {
+        Self {
+            peers: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Join a node to hub, got network of this node.
+    ///
+    /// `signer` is called with round for round change vote, without it for epoch vote.
+    pub fn join(
+        &self,
+        node_id: C::NodeId,
+        signer: impl Fn(&C::EpochId, &C::EpochHash, Option<u64>) -> Option<VoteSign<C::Signature>>
+            + Send
+            + Sync
+            + 'static,
+    ) -> ChannelNetwork<C, Q> {
+        let (sender, recver) = Q::unbounded();
+
+        self.peers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push((node_id.clone(), sender));
+
+        ChannelNetwork {
+            node_id,
+            hub: self.clone(),
+            signer: Arc::new(signer),
+            recver,
+        }
+    }
+}
+
+/// In-process network based on channel of runtime.
+///
+/// Broadcast packet is also sent to this node.
+pub struct ChannelNetwork<C: Consensus, Q: Queue<Envelope<C>>> {
+    node_id: C::NodeId,
+    hub: ChannelHub<C, Q>,
+    signer: VoteSigner<C>,
+    recver: Q::Receiver,
+}
+
+impl<C, Q> Network<C> for ChannelNetwork<C, Q>
+where
+    C: Consensus,
+    C::NodeId: Send + 'static,
+    C::EpochId: Send + 'static,
+    C::EpochHash: Send + 'static,
+    C::Signature: Send + 'static,
+    Q: Queue<Envelope<C>>,
+{
+    type Error = ChannelClosed;
+
+    fn node_id(&self) -> C::NodeId {
+        self.node_id.clone()
+    }
+
+    fn send_unsigned(
+        &self,
+        target: Option<C::NodeId>,
+        mut pkt: Packet<C::EpochId, C::EpochHash, C::Signature>,
+    ) {
+        match &mut pkt {
+            Packet::BroadcastPropose(p) => {
+                p.vote_sign = (self.signer)(&p.epoch_id, &p.epoch_hash, None)
+            }
+            Packet::ResponsePropose(p) => {
+                p.vote_sign = (self.signer)(&p.epoch_id, &p.epoch_hash, None)
+            }
+            // Round change is signed as vote on round after latest committed epoch.
+            Packet::RoundChange(p) => {
+                p.vote_sign = (self.signer)(&p.epoch_id, &p.epoch_hash, Some(p.round))
+            }
+            Packet::BroadcastCommit(_) | Packet::NewRound(_) | Packet::Heartbeat(_) => {}
+        }
+
+        let peers = self.hub.peers.lock().unwrap_or_else(|e| e.into_inner());
+
+        for (node_id, sender) in peers.iter() {
+            if target.as_ref().is_none_or(|t| t == node_id) {
+                // Peer dropped, ignore it.
+                Q::send(sender, (pkt.clone(), self.node_id.clone()));
+            }
+        }
+    }
+
+    type RecvFuture =
+        Pin<Box<dyn Future<Output = core::result::Result<Envelope<C>, ChannelClosed>> + Send>>;
+
+    fn recv(&self) -> Self::RecvFuture {
+        let recv = Q::recv(&self.recver);
+
+        Box::pin(async move { recv.await.ok_or(ChannelClosed) })
+    }
+
+    fn sign_vote(
+        &self,
+        epoch_id: &C::EpochId,
+        epoch_hash: &C::EpochHash,
+    ) -> Option<VoteSign<C::Signature>> {
+        (self.signer)(epoch_id, epoch_hash, None)
+    }
+}
@@ -0,0 +1,29 @@
+//! Adapters for async runtimes.
+//!
+//! Each adapter provide timer, in-process channel `Network` and helper to spawn engine.
+
+use alloc::sync::Arc;
+
+use crate::{Consensus, VoteSign};
+
+mod channel;
+
+#[cfg(feature = "tokio")]
+pub mod tokio;
+
+#[cfg(feature = "smol")]
+pub mod smol;
+
+/// Sign vote of epoch for this node, round is set for round change vote.
+///
+/// In-process networks trust all peers, this function only build `VoteSign` of this node.
+/// Return `None` if this node isn't a voter.
+pub type VoteSigner<C> = Arc<
+    dyn Fn(
+            &<C as Consensus>::EpochId,
+            &<C as Consensus>::EpochHash,
+            Option<u64>,
+        ) -> Option<VoteSign<<C as Consensus>::Signature>>
+        + Send
+        + Sync,
+>;
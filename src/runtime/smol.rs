@@ -0,0 +1,80 @@
+//! Smol runtime adapter.
+//!
+//! Also work with async-std, which is based on same reactor.
+
+use core::{future::Future, pin::Pin, time::Duration};
+
+use alloc::boxed::Box;
+
+use ::smol::{
+    channel::{unbounded, Receiver, Sender},
+    LocalExecutor, Task,
+};
+
+use crate::{algorithm::BRaft, App, Consensus, Network, Result};
+
+use super::channel;
+pub use super::channel::{ChannelClosed, Queue};
+
+/// Timer future, can be used as `Consensus::Timer`.
+pub type Timer = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// Build a timer fired after `duration`.
+pub fn timer(duration: Duration) -> Timer {
+    Box::pin(async move {
+        ::smol::Timer::after(duration).await;
+    })
+}
+
+/// Spawn engine to executor, run until shutdown.
+///
+/// Engine isn't `Send`, so it must run on a `LocalExecutor`.
+pub fn spawn_local<'a, N, A, C>(
+    executor: &LocalExecutor<'a>,
+    mut braft: BRaft<N, A, C>,
+) -> Task<Result<()>>
+where
+    N: Network<C> + 'a,
+    A: App<C> + 'a,
+    C: Consensus + 'a,
+{
+    executor.spawn(async move { braft.run().await })
+}
+
+/// Run engine on current thread until shutdown.
+pub fn block_on<N, A, C>(mut braft: BRaft<N, A, C>) -> Result<()>
+where
+    N: Network<C>,
+    A: App<C>,
+    C: Consensus,
+{
+    ::smol::block_on(braft.run())
+}
+
+/// Unbounded smol channel.
+pub struct SmolQueue;
+
+impl<T: Send + 'static> Queue<T> for SmolQueue {
+    type Sender = Sender<T>;
+    type Receiver = Receiver<T>;
+
+    fn unbounded() -> (Sender<T>, Receiver<T>) {
+        unbounded()
+    }
+
+    fn send(sender: &Sender<T>, msg: T) {
+        let _ = sender.try_send(msg);
+    }
+
+    fn recv(receiver: &Receiver<T>) -> Pin<Box<dyn Future<Output = Option<T>> + Send>> {
+        let receiver = receiver.clone();
+
+        Box::pin(async move { receiver.recv().await.ok() })
+    }
+}
+
+/// Hub of in-process nodes.
+pub type ChannelHub<C> = channel::ChannelHub<C, SmolQueue>;
+
+/// In-process network based on smol channel.
+pub type ChannelNetwork<C> = channel::ChannelNetwork<C, SmolQueue>;
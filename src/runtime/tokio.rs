@@ -0,0 +1,91 @@
+//! Tokio runtime adapter.
+
+use core::{future::Future, pin::Pin, time::Duration};
+
+use alloc::{boxed::Box, sync::Arc};
+
+use ::tokio::{
+    sync::{mpsc, Mutex as AsyncMutex},
+    task::JoinHandle,
+};
+
+use crate::{algorithm::BRaft, App, Consensus, Network, Result};
+
+use super::channel;
+pub use super::channel::{ChannelClosed, Queue};
+
+/// Timer future, can be used as `Consensus::Timer`.
+pub type Timer = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// Build a timer fired after `duration`.
+pub fn timer(duration: Duration) -> Timer {
+    Box::pin(::tokio::time::sleep(duration))
+}
+
+/// Spawn engine to current runtime, run until shutdown.
+///
+/// Network, app and consensus, with their futures, must be `Send`, use `spawn_local` if not.
+pub fn spawn<N, A, C>(mut braft: BRaft<N, A, C>) -> JoinHandle<Result<()>>
+where
+    N: Network<C> + Send + 'static,
+    N::RecvFuture: Send,
+    A: App<C> + Send + 'static,
+    A::ProposeEpochFuture: Send,
+    A::EnterStepFuture: Send,
+    A::CommitFuture: Send,
+    A::ShutdownFuture: Send,
+    C: Consensus + Send + 'static,
+    C::Timer: Send,
+    C::LatestVoterSetFuture: Send,
+    C::ComputeProposerFuture: Send,
+    C::NodeId: Send + Sync,
+    C::EpochId: Send + Sync,
+    C::EpochHash: Send + Sync,
+    C::Weight: Send + Sync,
+    C::PublicKey: Send + Sync,
+    C::Signature: Send + Sync,
+{
+    ::tokio::spawn(async move { braft.run().await })
+}
+
+/// Spawn engine to current `LocalSet`, run until shutdown.
+///
+/// Engine with types which aren't `Send` must run on a `LocalSet`.
+pub fn spawn_local<N, A, C>(mut braft: BRaft<N, A, C>) -> JoinHandle<Result<()>>
+where
+    N: Network<C> + 'static,
+    A: App<C> + 'static,
+    C: Consensus + 'static,
+{
+    ::tokio::task::spawn_local(async move { braft.run().await })
+}
+
+/// Unbounded tokio channel, receiver is shared by receives in flight.
+pub struct TokioQueue;
+
+impl<T: Send + 'static> Queue<T> for TokioQueue {
+    type Sender = mpsc::UnboundedSender<T>;
+    type Receiver = Arc<AsyncMutex<mpsc::UnboundedReceiver<T>>>;
+
+    fn unbounded() -> (Self::Sender, Self::Receiver) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+
+        (sender, Arc::new(AsyncMutex::new(receiver)))
+    }
+
+    fn send(sender: &Self::Sender, msg: T) {
+        let _ = sender.send(msg);
+    }
+
+    fn recv(receiver: &Self::Receiver) -> Pin<Box<dyn Future<Output = Option<T>> + Send>> {
+        let receiver = receiver.clone();
+
+        Box::pin(async move { receiver.lock().await.recv().await })
+    }
+}
+
+/// Hub of in-process nodes.
+pub type ChannelHub<C> = channel::ChannelHub<C, TokioQueue>;
+
+/// In-process network based on tokio channel.
+pub type ChannelNetwork<C> = channel::ChannelNetwork<C, TokioQueue>;
@@ -0,0 +1,43 @@
+//! Status of consensus engine.
+
+use core::time::Duration;
+
+use alloc::vec::Vec;
+
+use crate::{Consensus, Role, VoterConfig};
+
+/// Read-only snapshot of engine state.
+#[derive(Debug, Clone)]
+pub struct Status<N, P, I, H, W> {
+    pub node_id: N,
+    pub role: Role,
+    /// Proposer of current epoch.
+    pub proposer: N,
+
+    /// Latest committed epoch.
+    pub latest_epoch_id: I,
+    /// Epoch in consensus.
+    pub epoch_id: I,
+    pub epoch_hash: H,
+    pub round: u64,
+    pub step: u8,
+
+    /// Weight collected on current round.
+    pub weight: W,
+    /// Voters of current epoch.
+    pub voters: VoterConfig<N, P, W>,
+    /// Voters which signed current epoch.
+    pub vote_signers: Vec<N>,
+
+    /// Time of last commit, from `Consensus::now`.
+    pub last_commit_at: Option<Duration>,
+}
+
+/// Status of a consensus.
+pub type ConsensusStatus<C> = Status<
+    <C as Consensus>::NodeId,
+    <C as Consensus>::PublicKey,
+    <C as Consensus>::EpochId,
+    <C as Consensus>::EpochHash,
+    <C as Consensus>::Weight,
+>;
@@ -0,0 +1,170 @@
+//! Timeout policy of step timer.
+
+use core::time::Duration;
+
+use alloc::vec::Vec;
+
+use crate::Role;
+
+/// Timeout policy
+///
+/// Timeout of step is computed as:
+///
+/// `min(base * step_multiplier * backoff ^ round, max) + random(0..jitter)`
+///
+/// Growing timeout on round make nodes on slow links eventually synchronize.
+/// Jitter avoid nodes timing out at same time. Arithmetic saturates, overflow is capped at max.
+#[derive(Debug, Clone)]
+pub struct TimeoutPolicy {
+    base: Duration,
+    step_multipliers: Vec<(Role, u8, f64)>,
+    backoff: f64,
+    max: Duration,
+    jitter: Duration,
+    quorum_grace: Duration,
+    heartbeat: Duration,
+}
+
+impl Default for TimeoutPolicy {
+    /// One second for all steps, without backoff and jitter.
+    fn default() -> Self {
+        Self::new(Duration::from_secs(1))
+    }
+}
+
+impl TimeoutPolicy {
+    /// Build policy with base timeout.
+    ///
+    /// Default has no backoff, jitter and quorum grace, max is 60 seconds, heartbeat interval is
+    /// half of base.
+    ///
+    /// # Panics
+    ///
+    /// Panics if base is zero, engine would spin without waiting.
+    pub fn new(base: Duration) -> Self {
+        assert!(!base.is_zero(), "invalid base timeout: zero");
+
+        Self {
+            base,
+            step_multipliers: Vec::new(),
+            backoff: 1.0,
+            max: Duration::from_secs(60),
+            jitter: Duration::ZERO,
+            quorum_grace: Duration::ZERO,
+            heartbeat: base / 2,
+        }
+    }
+
+    /// Set multiplier of step for role.
+    ///
+    /// # Panics
+    ///
+    /// Panics if multiplier is not positive or not finite.
+    pub fn with_step_multiplier(mut self, role: Role, step: u8, multiplier: f64) -> Self {
+        assert!(
+            multiplier.is_finite() && multiplier > 0.0,
+            "invalid step multiplier: {}",
+            multiplier
+        );
+
+        self.step_multipliers
+            .retain(|(r, s, _)| !(*r == role && *s == step));
+        self.step_multipliers.push((role, step, multiplier));
+        self
+    }
+
+    /// Set backoff factor on each round, factor not above 1 disables backoff.
+    ///
+    /// # Panics
+    ///
+    /// Panics if backoff is not finite.
+    pub fn with_backoff(mut self, backoff: f64) -> Self {
+        assert!(backoff.is_finite(), "invalid backoff: {}", backoff);
+
+        self.backoff = backoff;
+        self
+    }
+
+    /// Set max timeout, jitter not included.
+    ///
+    /// # Panics
+    ///
+    /// Panics if max is zero.
+    pub fn with_max(mut self, max: Duration) -> Self {
+        assert!(!max.is_zero(), "invalid max timeout: zero");
+
+        self.max = max;
+        self
+    }
+
+    /// Set max jitter.
+    pub fn with_jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Set time proposer keeps collecting votes after quorum reached.
+    ///
+    /// More signatures are included in commit. Zero commits as soon as quorum reached.
+    pub fn with_quorum_grace(mut self, quorum_grace: Duration) -> Self {
+        self.quorum_grace = quorum_grace;
+        self
+    }
+
+    /// Time proposer keeps collecting votes after quorum reached.
+    pub fn quorum_grace(&self) -> Duration {
+        self.quorum_grace
+    }
+
+    /// Set interval of heartbeat sent by idle stable leader.
+    ///
+    /// Should be shorter than timeout of follower.
+    pub fn with_heartbeat(mut self, heartbeat: Duration) -> Self {
+        self.heartbeat = heartbeat;
+        self
+    }
+
+    /// Interval of heartbeat sent by idle stable leader.
+    pub fn heartbeat(&self) -> Duration {
+        self.heartbeat
+    }
+
+    /// Compute timeout of step on round.
+    ///
+    /// `entropy` is a random number to compute jitter.
+    pub fn timeout(&self, role: &Role, step: u8, round: u64, entropy: u64) -> Duration {
+        let multiplier = self
+            .step_multipliers
+            .iter()
+            .find(|(r, s, _)| r == role && *s == step)
+            .map(|(_, _, m)| *m)
+            .unwrap_or(1.0);
+
+        let mut timeout = self.mul(self.base, multiplier);
+
+        if self.backoff > 1.0 {
+            for _ in 0..round {
+                if timeout >= self.max {
+                    break;
+                }
+                timeout = self.mul(timeout, self.backoff);
+            }
+        }
+
+        let timeout = timeout.min(self.max);
+
+        if self.jitter.is_zero() {
+            timeout
+        } else {
+            let jitter = u64::try_from(self.jitter.as_nanos()).unwrap_or(u64::MAX);
+            timeout.saturating_add(Duration::from_nanos(entropy % jitter.saturating_add(1)))
+        }
+    }
+
+    /// Multiply duration, capped at max.
+    fn mul(&self, duration: Duration, factor: f64) -> Duration {
+        Duration::try_from_secs_f64(duration.as_secs_f64() * factor)
+            .unwrap_or(self.max)
+            .min(self.max)
+    }
+}
@@ -13,7 +13,7 @@ pub struct Voter<V, P, W> {
 /// Signature for voter
 ///
 /// `idx` is the order of voter set.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct VoteSign<S> {
     pub idx: u64,
     pub sign: S,
@@ -23,7 +23,7 @@ pub struct VoteSign<S> {
 ///
 /// Proposer and Follower is Voter, do consensus among these.
 /// Observer only sync data from other node.
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Role {
     Proposer,
     Follower,
@@ -39,7 +39,7 @@ impl Role {
         matches!(self, Role::Follower)
     }
 
-    /*     pub(crate) fn is_observer(&self) -> bool { */
-    /* matches!(self, Role::Observer) */
-    /* } */
+    pub(crate) fn is_observer(&self) -> bool {
+        matches!(self, Role::Observer)
+    }
 }
@@ -0,0 +1,211 @@
+use alloc::vec::Vec;
+
+use crate::{quorum::FaultTolerance, Error, NodeId, Result, Voter, Weight};
+
+/// Voter set
+///
+/// Checked array of voters. The index of voter in this set is the `idx` of `VoteSign`.
+///
+/// A voter set must be non-empty, each `voter_id` must be unique and each weight must be
+/// non-zero.
+#[derive(Debug, Clone)]
+pub struct VoterSet<V, P, W> {
+    voters: Vec<Voter<V, P, W>>,
+    total_weight: W,
+}
+
+impl<V, P, W> VoterSet<V, P, W>
+where
+    V: NodeId,
+    W: Weight,
+{
+    /// Check voters and build voter set.
+    pub fn new(voters: Vec<Voter<V, P, W>>) -> Result<Self> {
+        if voters.is_empty() {
+            return Err(Error::EmptyVoterSet);
+        }
+
+        for (idx, voter) in voters.iter().enumerate() {
+            if voter.weight.is_zero() {
+                return Err(Error::ZeroWeightVoter(idx as u64));
+            }
+
+            if voters[..idx].iter().any(|v| v.voter_id == voter.voter_id) {
+                return Err(Error::DuplicateVoter(idx as u64));
+            }
+        }
+
+        let total_weight = voters.iter().map(|e| e.weight.clone()).sum();
+
+        Ok(Self {
+            voters,
+            total_weight,
+        })
+    }
+
+    /// Sum of all voters' weight.
+    pub fn total_weight(&self) -> &W {
+        &self.total_weight
+    }
+
+    /// All voters, ordered by index.
+    pub fn voters(&self) -> &[Voter<V, P, W>] {
+        &self.voters
+    }
+
+    /// Get voter by index.
+    pub fn get(&self, idx: u64) -> Option<&Voter<V, P, W>> {
+        self.voters.get(idx as usize)
+    }
+
+    /// Get index of voter.
+    pub fn index_of(&self, voter_id: &V) -> Option<u64> {
+        self.voters
+            .iter()
+            .position(|v| &v.voter_id == voter_id)
+            .map(|i| i as u64)
+    }
+
+    /// Check node is a voter.
+    pub fn contains(&self, voter_id: &V) -> bool {
+        self.index_of(voter_id).is_some()
+    }
+
+    /// Number of voters.
+    pub fn len(&self) -> usize {
+        self.voters.len()
+    }
+
+    /// Always false, voter set can't be empty.
+    pub fn is_empty(&self) -> bool {
+        self.voters.is_empty()
+    }
+
+    /// Check two voter sets have same voters and weights.
+    pub fn same_voters(&self, other: &Self) -> bool {
+        self.len() == other.len()
+            && self
+                .voters
+                .iter()
+                .zip(other.voters.iter())
+                .all(|(a, b)| a.voter_id == b.voter_id && a.weight == b.weight)
+    }
+}
+
+/// Voters of a epoch
+///
+/// `Joint` is used on the transition epoch of a voter set change. Votes must reach a majority
+/// in both the old and the new voter set. After the transition epoch committed, the new voter
+/// set takes over alone.
+///
+/// On `Joint`, index of voter is the order of old voters followed by new voters not in old
+/// voter set.
+#[derive(Debug, Clone)]
+pub enum VoterConfig<V, P, W> {
+    Single(VoterSet<V, P, W>),
+    Joint {
+        old: VoterSet<V, P, W>,
+        new: VoterSet<V, P, W>,
+    },
+}
+
+impl<V, P, W> VoterConfig<V, P, W>
+where
+    V: NodeId,
+    P: Clone,
+    W: Weight,
+{
+    /// Voter set after this epoch.
+    pub fn latest(&self) -> &VoterSet<V, P, W> {
+        match self {
+            Self::Single(s) => s,
+            Self::Joint { new, .. } => new,
+        }
+    }
+
+    /// Check this epoch is a transition epoch.
+    pub fn is_joint(&self) -> bool {
+        matches!(self, Self::Joint { .. })
+    }
+
+    /// Voters of this epoch, in order of index.
+    pub fn voters(&self) -> Vec<&Voter<V, P, W>> {
+        match self {
+            Self::Single(s) => s.voters().iter().collect(),
+            Self::Joint { old, new } => old
+                .voters()
+                .iter()
+                .chain(new.voters().iter().filter(|v| !old.contains(&v.voter_id)))
+                .collect(),
+        }
+    }
+
+    /// Get voter by index.
+    pub fn get(&self, idx: u64) -> Option<&Voter<V, P, W>> {
+        match self {
+            Self::Single(s) => s.get(idx),
+            Self::Joint { old, new } => old.get(idx).or_else(|| {
+                new.voters()
+                    .iter()
+                    .filter(|v| !old.contains(&v.voter_id))
+                    .nth(idx as usize - old.len())
+            }),
+        }
+    }
+
+    /// Get index of voter.
+    pub fn index_of(&self, voter_id: &V) -> Option<u64> {
+        match self {
+            Self::Single(s) => s.index_of(voter_id),
+            Self::Joint { old, new } => old.index_of(voter_id).or_else(|| {
+                new.voters()
+                    .iter()
+                    .filter(|v| !old.contains(&v.voter_id))
+                    .position(|v| &v.voter_id == voter_id)
+                    .map(|i| (i + old.len()) as u64)
+            }),
+        }
+    }
+
+    /// Check node is a voter of this epoch.
+    pub fn contains(&self, voter_id: &V) -> bool {
+        self.index_of(voter_id).is_some()
+    }
+
+    /// Check voters of index reach quorum.
+    ///
+    /// On `Joint`, quorum is needed in both voter sets.
+    pub fn has_quorum(&self, idxs: &[u64], fault_tolerance: FaultTolerance) -> bool {
+        match self {
+            Self::Single(s) => fault_tolerance.has_quorum(s, idxs),
+            Self::Joint { old, new } => {
+                fault_tolerance.has_quorum(old, &self.indexes_in(old, idxs))
+                    && fault_tolerance.has_quorum(new, &self.indexes_in(new, idxs))
+            }
+        }
+    }
+
+    /// Map index of this config to index of voter set.
+    fn indexes_in(&self, voter_set: &VoterSet<V, P, W>, idxs: &[u64]) -> Vec<u64> {
+        idxs.iter()
+            .filter_map(|idx| self.get(*idx))
+            .filter_map(|v| voter_set.index_of(&v.voter_id))
+            .collect()
+    }
+
+    /// Build voters of next epoch.
+    ///
+    /// If `joint` is true and voters changed, next epoch is a transition epoch.
+    pub fn next(&self, voter_set: VoterSet<V, P, W>, joint: bool) -> Self {
+        let latest = self.latest();
+
+        if joint && !latest.same_voters(&voter_set) {
+            Self::Joint {
+                old: latest.clone(),
+                new: voter_set,
+            }
+        } else {
+            Self::Single(voter_set)
+        }
+    }
+}
@@ -1,5 +1,10 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
 use braft_test::{SingleApp, SingleConsensus, SingleNetwork};
-use consensus_rs::algorithm::BRaft;
+use consensus_rs::{algorithm::BRaft, event::EventKind, Role};
 
 mod braft_test;
 mod utils;
@@ -15,7 +20,7 @@ fn signle_node() {
     let braft = BRaft::new(network, consensus, app);
 
     smol::block_on(async move {
-        let mut braft = braft.await;
+        let mut braft = braft.await.unwrap();
 
         for _ in 0..30 {
             braft.do_tick().await.unwrap();
@@ -34,7 +39,7 @@ fn signle_follower_node() {
     let braft = BRaft::new(network, consensus, app);
 
     smol::block_on(async move {
-        let mut braft = braft.await;
+        let mut braft = braft.await.unwrap();
 
         for _ in 0..30 {
             braft.do_tick().await.unwrap();
@@ -42,5 +47,266 @@ fn signle_follower_node() {
     })
 }
 
+#[test]
+fn single_node_events() {
+    utils::init();
+
+    let network = SingleNetwork::new();
+    let app = SingleApp::new();
+    let consensus = SingleConsensus::new(vec![1]);
+
+    let braft = BRaft::new(network, consensus, app);
+
+    smol::block_on(async move {
+        let mut braft = braft.await.unwrap();
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let listener_events = events.clone();
+        braft.subscribe(move |e| listener_events.lock().unwrap().push(e.clone()));
+
+        for _ in 0..4 {
+            braft.do_tick().await.unwrap();
+        }
+
+        let events = events.lock().unwrap();
+
+        let committed: Vec<u64> = events
+            .iter()
+            .filter_map(|e| match &e.kind {
+                EventKind::Committed { epoch_id, .. } => Some(*epoch_id),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(committed, vec![1, 2]);
+
+        assert!(events
+            .iter()
+            .any(|e| matches!(e.kind, EventKind::VoteCounted { idx: 0, weight: 1 })));
+        assert!(events
+            .iter()
+            .any(|e| matches!(e.kind, EventKind::StepChanged { from: 0, to: 1 })));
+    })
+}
+
+#[test]
+fn single_node_status() {
+    utils::init();
+
+    let network = SingleNetwork::new();
+    let app = SingleApp::new();
+    let consensus = SingleConsensus::new(vec![1]);
+
+    let braft = BRaft::new(network, consensus, app);
+
+    smol::block_on(async move {
+        let mut braft = braft.await.unwrap();
+
+        let status = braft.status();
+        assert_eq!(status.role, Role::Proposer);
+        assert_eq!(status.proposer, vec![1]);
+        assert_eq!(status.latest_epoch_id, 0);
+
+        braft.do_tick().await.unwrap();
+
+        let status = braft.status();
+        assert_eq!(status.step, 1);
+        assert_eq!(status.epoch_id, 1);
+
+        braft.do_tick().await.unwrap();
+
+        let status = braft.status();
+        assert_eq!(status.latest_epoch_id, 1);
+        assert_eq!(status.step, 0);
+        assert_eq!(status.voters.latest().len(), 1);
+        assert!(status.last_commit_at.is_none());
+    })
+}
+
+#[test]
+fn single_node_run_shutdown() {
+    utils::init();
+
+    let network = SingleNetwork::new();
+    let app = SingleApp::new();
+    let consensus = SingleConsensus::new(vec![1]);
+
+    let braft = BRaft::new(network, consensus, app);
+
+    smol::block_on(async move {
+        let mut braft = braft.await.unwrap();
+
+        let handle = braft.shutdown_handle();
+
+        let stopper = async move {
+            smol::Timer::after(Duration::from_millis(2500)).await;
+            handle.shutdown();
+        };
+
+        let (res, _) = futures_lite::future::zip(braft.run(), stopper).await;
+        res.unwrap();
+
+        // Commit on quorum, not paced by step timer.
+        let status = braft.status();
+        assert!(status.latest_epoch_id >= 3);
+    })
+}
+
+#[test]
+fn single_node_status_on_commit() {
+    utils::init();
+
+    let network = SingleNetwork::signed();
+    let app = SingleApp::new();
+    let mut consensus = SingleConsensus::new(vec![1]);
+    consensus.clock = Some(Duration::from_secs(42));
+
+    let braft = BRaft::new(network, consensus, app);
+
+    smol::block_on(async move {
+        let mut braft = braft.await.unwrap();
+
+        // Proposer counted its own signed vote on propose.
+        braft.do_tick().await.unwrap();
+        let status = braft.status();
+        assert_eq!(status.step, 1);
+        assert_eq!(status.vote_signers, vec![vec![1]]);
+        assert!(status.last_commit_at.is_none());
+
+        braft.do_tick().await.unwrap();
+        let status = braft.status();
+        assert_eq!(status.latest_epoch_id, 1);
+        assert_eq!(status.last_commit_at, Some(Duration::from_secs(42)));
+        assert!(status.vote_signers.is_empty());
+    })
+}
+
+#[test]
+fn shutdown_waiting_tick() {
+    utils::init();
+
+    // Follower waits a second for proposal.
+    let network = SingleNetwork::new();
+    let app = SingleApp::new();
+    let consensus = SingleConsensus::new(vec![2]);
+
+    let braft = BRaft::new(network, consensus, app);
+
+    smol::block_on(async move {
+        let mut braft = braft.await.unwrap();
+
+        let handle = braft.shutdown_handle();
+
+        let stopper = async move {
+            smol::Timer::after(Duration::from_millis(50)).await;
+            handle.shutdown();
+        };
+
+        let start = Instant::now();
+        let (res, _) = futures_lite::future::zip(braft.run(), stopper).await;
+        res.unwrap();
+
+        assert!(start.elapsed() < Duration::from_millis(500));
+    })
+}
+
+#[test]
+fn finish_commit_on_shutdown() {
+    utils::init();
+
+    let network = SingleNetwork::new();
+    let mut app = SingleApp::new();
+    app.commit_delay = Duration::from_millis(200);
+    let consensus = SingleConsensus::new(vec![1]);
+
+    let braft = BRaft::new(network, consensus, app);
+
+    smol::block_on(async move {
+        let mut braft = braft.await.unwrap();
+
+        let handle = braft.shutdown_handle();
+
+        // Shutdown while app is committing epoch 1.
+        let stopper = async move {
+            smol::Timer::after(Duration::from_millis(50)).await;
+            handle.shutdown();
+        };
+
+        let (res, _) = futures_lite::future::zip(braft.run(), stopper).await;
+        res.unwrap();
+
+        let status = braft.status();
+        assert_eq!(status.latest_epoch_id, 1);
+        assert_eq!(status.step, 0);
+    })
+}
+
+#[test]
+fn keep_tick_error_on_shutdown_error() {
+    utils::init();
+
+    let network = SingleNetwork::new();
+    let mut app = SingleApp::new();
+    app.fail = true;
+    let consensus = SingleConsensus::new(vec![1]);
+
+    let braft = BRaft::new(network, consensus, app);
+
+    smol::block_on(async move {
+        let mut braft = braft.await.unwrap();
+
+        let err = braft.run().await.unwrap_err();
+        assert!(format!("{:?}", err).contains("commit failed"));
+    })
+}
+
+#[test]
+fn single_node_control() {
+    utils::init();
+
+    let network = SingleNetwork::new();
+    let app = SingleApp::new();
+    let consensus = SingleConsensus::new(vec![1]);
+
+    let braft = BRaft::new(network, consensus, app);
+
+    smol::block_on(async move {
+        let mut braft = braft.await.unwrap();
+
+        let handle = braft.control_handle();
+
+        handle.pause_proposing();
+        braft.do_tick().await.unwrap();
+        assert_eq!(braft.status().epoch_id, 0);
+
+        handle.resume_proposing();
+        handle.force_round_change();
+        let (status, res) = futures_lite::future::zip(handle.dump_status(), braft.do_tick()).await;
+        res.unwrap();
+
+        let status = status.unwrap();
+        assert_eq!(status.round, 1);
+        assert_eq!(status.step, 0);
+
+        // Propose once round is certified by own round change.
+        assert_eq!(braft.status().step, 0);
+        braft.do_tick().await.unwrap();
+        assert_eq!(braft.status().step, 1);
+
+        // Single voter is elected again on certified next round.
+        handle.step_down();
+        let (status, res) = futures_lite::future::zip(handle.dump_status(), braft.do_tick()).await;
+        res.unwrap();
+
+        let status = status.unwrap();
+        assert_eq!(status.round, 2);
+        assert_eq!(status.step, 0);
+
+        braft.do_tick().await.unwrap();
+        let status = braft.status();
+        assert_eq!(status.role, Role::Proposer);
+        assert_eq!(status.step, 1);
+    })
+}
+
 #[test]
 fn two_node() {}
@@ -1,2 +1,5 @@
+// Not all helpers are used by each test.
+#![allow(dead_code)]
+
 mod single;
 pub use single::*;
@@ -7,12 +7,16 @@ use smol::{
 };
 use std::{boxed::Box, string::String, vec::Vec};
 
-use consensus_rs::{packet::Packet, App, Consensus, Network, Role, VoteSign, Voter};
+use consensus_rs::{packet::Packet, App, Consensus, Network, VoteSign, Voter};
 
 pub struct SingleApp {
     pub epoch_id: u64,
     pub epoch_hash: u64,
     pub voter: Voter<Vec<u8>, Vec<u8>, u64>,
+    /// Fail commit and shutdown.
+    pub fail: bool,
+    /// Time taken by commit.
+    pub commit_delay: Duration,
 }
 
 impl SingleApp {
@@ -27,6 +31,8 @@ impl SingleApp {
             epoch_id: 0,
             epoch_hash: 0,
             voter,
+            fail: false,
+            commit_delay: Duration::ZERO,
         }
     }
 }
@@ -41,6 +47,8 @@ impl App<SingleConsensus> for SingleApp {
     type CommitFuture =
         Pin<Box<dyn Future<Output = Result<Vec<Voter<Vec<u8>, Vec<u8>, u64>>, Self::Error>>>>;
 
+    type ShutdownFuture = Pin<Box<dyn Future<Output = Result<(), Self::Error>>>>;
+
     fn propose_epoch(&mut self) -> Self::ProposeEpochFuture {
         let epoch_id = self.epoch_id + 1;
         let epoch_hash = self.epoch_hash + 1;
@@ -57,14 +65,37 @@ impl App<SingleConsensus> for SingleApp {
         self.epoch_hash = *epoch_hash;
 
         let voter = vec![self.voter.clone()];
+        let fail = self.fail;
+        let delay = self.commit_delay;
 
-        Box::pin(async move { Ok(voter) })
+        Box::pin(async move {
+            if !delay.is_zero() {
+                Timer::after(delay).await;
+            }
+            if fail {
+                return Err(String::from("commit failed"));
+            }
+            Ok(voter)
+        })
+    }
+
+    fn shutdown(&mut self) -> Self::ShutdownFuture {
+        let fail = self.fail;
+
+        Box::pin(async move {
+            if fail {
+                return Err(String::from("shutdown failed"));
+            }
+            Ok(())
+        })
     }
 }
 
 pub struct SingleConsensus {
     pub voter: Voter<Vec<u8>, Vec<u8>, u64>,
     pub proposer: Vec<u8>,
+    /// Time returned by `now`.
+    pub clock: Option<Duration>,
 }
 
 impl SingleConsensus {
@@ -75,7 +106,11 @@ impl SingleConsensus {
             weight: 1,
         };
 
-        Self { voter, proposer }
+        Self {
+            voter,
+            proposer,
+            clock: None,
+        }
     }
 }
 
@@ -94,12 +129,16 @@ impl Consensus for SingleConsensus {
 
     type Signature = Vec<u8>;
 
-    fn step_timer(&self, _role: &Role, _step: u8) -> Self::Timer {
+    fn timer(&self, timeout: Duration) -> Self::Timer {
         Box::pin(async move {
-            Timer::after(Duration::from_secs(1)).await;
+            Timer::after(timeout).await;
         })
     }
 
+    fn now(&self) -> Option<Duration> {
+        self.clock
+    }
+
     type LatestEpochFuture = Pin<Box<dyn Future<Output = (u64, u64)>>>;
 
     fn latest_epoch(&self) -> Self::LatestEpochFuture {
@@ -127,13 +166,27 @@ impl Consensus for SingleConsensus {
 pub struct SingleNetwork {
     sender: Sender<Packet<u64, u64, Vec<u8>>>,
     recver: Receiver<Packet<u64, u64, Vec<u8>>>,
+    /// Sign own vote on propose, not only on loopback.
+    signed: bool,
 }
 
 impl SingleNetwork {
     pub fn new() -> Self {
         let (sender, recver) = unbounded();
 
-        Self { sender, recver }
+        Self {
+            sender,
+            recver,
+            signed: false,
+        }
+    }
+
+    /// Network which signs own vote, proposer counts it on propose.
+    pub fn signed() -> Self {
+        Self {
+            signed: true,
+            ..Self::new()
+        }
     }
 }
 
@@ -181,10 +234,18 @@ impl Network<SingleConsensus> for SingleNetwork {
             match &mut pkt {
                 Packet::BroadcastPropose(rp) => rp.vote_sign = Some(sign),
                 Packet::ResponsePropose(rp) => rp.vote_sign = Some(sign),
+                Packet::RoundChange(rc) => rc.vote_sign = Some(sign),
                 _ => {}
             }
 
             Ok((pkt, node_id))
         })
     }
+
+    fn sign_vote(&self, _epoch_id: &u64, _epoch_hash: &u64) -> Option<VoteSign<Vec<u8>>> {
+        self.signed.then(|| VoteSign {
+            idx: 0,
+            sign: vec![1],
+        })
+    }
 }
@@ -0,0 +1,137 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
+
+use cluster::{voters, ClusterConsensus};
+use consensus_rs::{
+    network::{BroadcastNetwork, Fanout},
+    packet::Packet,
+    Network, VoterConfig, VoterSet,
+};
+use smol::channel::{self, Receiver, RecvError, Sender};
+
+mod cluster;
+
+type TestPacket = Packet<u64, u64, Vec<u8>>;
+
+type Sent = Arc<Mutex<Vec<(Option<Vec<u8>>, &'static str)>>>;
+
+/// Network records sent packets, receives injected packets.
+struct RecordNetwork {
+    node_id: Vec<u8>,
+    sent: Sent,
+    recver: Receiver<(TestPacket, Vec<u8>)>,
+}
+
+impl Network<ClusterConsensus> for RecordNetwork {
+    type Error = RecvError;
+
+    fn node_id(&self) -> Vec<u8> {
+        self.node_id.clone()
+    }
+
+    fn send_unsigned(&self, target: Option<Vec<u8>>, pkt: TestPacket) {
+        self.sent.lock().unwrap().push((target, pkt.name()));
+    }
+
+    type RecvFuture = Pin<Box<dyn Future<Output = Result<(TestPacket, Vec<u8>), RecvError>>>>;
+
+    fn recv(&self) -> Self::RecvFuture {
+        let recver = self.recver.clone();
+
+        Box::pin(async move { recver.recv().await })
+    }
+}
+
+type Fixture = (
+    BroadcastNetwork<ClusterConsensus, RecordNetwork>,
+    Sent,
+    Sender<(TestPacket, Vec<u8>)>,
+);
+
+fn fixture(node_id: u8, fanout: Fanout) -> Fixture {
+    let sent = Sent::default();
+    let (sender, recver) = channel::unbounded();
+
+    let inner = RecordNetwork {
+        node_id: vec![node_id],
+        sent: sent.clone(),
+        recver,
+    };
+
+    let mut network = BroadcastNetwork::new(inner, vec![vec![4], vec![5], vec![6]], fanout);
+    network.update_voters(&VoterConfig::Single(VoterSet::new(voters(3)).unwrap()));
+
+    (network, sent, sender)
+}
+
+fn targets(sent: &Sent) -> Vec<u8> {
+    sent.lock()
+        .unwrap()
+        .drain(..)
+        .map(|(t, _)| t.unwrap()[0])
+        .collect()
+}
+
+#[test]
+fn voter_first() {
+    let (network, sent, _sender) = fixture(1, Fanout::Direct);
+
+    network.send_unsigned(None, Packet::broadcast_propose_from_id_hash(1, 100, 0));
+    assert_eq!(targets(&sent), vec![1, 2, 3]);
+
+    // Observers got commit after voters.
+    network.send_unsigned(None, Packet::broadcast_commit_from_id_hash(1, 100, vec![]));
+    assert_eq!(targets(&sent), vec![1, 2, 3, 4, 5, 6]);
+
+    network.send_unsigned(
+        Some(vec![2]),
+        Packet::response_propose_from_id_hash(2, 200, 0),
+    );
+    assert_eq!(targets(&sent), vec![2]);
+
+    let handoffs = network.handoffs();
+    let get = |id: u8| handoffs.iter().find(|h| h.node_id == vec![id]).unwrap();
+    assert_eq!(get(2).sent, 3);
+    assert_eq!(get(2).commit, Some(1));
+    assert_eq!(get(3).sent, 2);
+    assert_eq!(get(4).sent, 1);
+    assert_eq!(get(4).commit, Some(1));
+}
+
+#[test]
+fn relay_commit() {
+    let (network, sent, sender) = fixture(2, Fanout::Relay { redundancy: 1 });
+
+    // Commit isn't sent to observers by broadcaster.
+    network.send_unsigned(None, Packet::broadcast_commit_from_id_hash(1, 100, vec![]));
+    network.flush();
+    assert_eq!(targets(&sent), vec![1, 2, 3]);
+
+    for _ in 0..2 {
+        sender
+            .try_send((
+                Packet::broadcast_commit_from_id_hash(1, 100, vec![]),
+                vec![1],
+            ))
+            .unwrap();
+        smol::block_on(network.recv()).unwrap();
+    }
+
+    // Relayed once to its share of observers.
+    network.flush();
+    assert_eq!(targets(&sent), vec![5]);
+
+    let (network, sent, sender) = fixture(2, Fanout::Relay { redundancy: 2 });
+    sender
+        .try_send((
+            Packet::broadcast_commit_from_id_hash(1, 100, vec![]),
+            vec![1],
+        ))
+        .unwrap();
+    smol::block_on(network.recv()).unwrap();
+    network.flush();
+    assert_eq!(targets(&sent), vec![4, 5]);
+}
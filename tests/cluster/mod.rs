@@ -0,0 +1,272 @@
+// Not all helpers are used by each test.
+#![allow(dead_code)]
+
+use core::{pin::Pin, time::Duration};
+use std::{
+    sync::{Arc, Mutex},
+    vec::Vec,
+};
+
+use futures_lite::Future;
+
+use consensus_rs::{timeout::TimeoutPolicy, App, Consensus, Signer, Transport, VoteSign, Voter};
+use smol::channel::{self, Receiver, RecvError, Sender};
+
+pub type BoxTimer = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// Timer on smol reactor.
+pub fn timer(duration: Duration) -> BoxTimer {
+    Box::pin(async move {
+        smol::Timer::after(duration).await;
+    })
+}
+
+/// Vote signature of voter, raw network doesn't verify it.
+pub fn sign(idx: u64) -> VoteSign<Vec<u8>> {
+    VoteSign { idx, sign: vec![] }
+}
+
+pub fn voters(n: u8) -> Vec<Voter<Vec<u8>, Vec<u8>, u64>> {
+    (1..=n)
+        .map(|i| Voter {
+            voter_id: vec![i],
+            public_key: vec![i],
+            weight: 1,
+        })
+        .collect()
+}
+
+pub fn vote_signer(
+    node_id: u8,
+    n: u8,
+) -> impl Fn(&u64, &u64, Option<u64>) -> Option<VoteSign<Vec<u8>>> + Send + Sync + 'static {
+    move |_, _, _| {
+        if node_id <= n {
+            Some(VoteSign {
+                idx: (node_id - 1) as u64,
+                sign: vec![node_id],
+            })
+        } else {
+            None
+        }
+    }
+}
+
+/// Signature is public key followed by hash of message.
+pub struct ClusterSigner(pub Vec<u8>);
+
+fn fnv(msg: &[u8]) -> [u8; 8] {
+    msg.iter()
+        .fold(0xcbf2_9ce4_8422_2325u64, |h, b| {
+            (h ^ *b as u64).wrapping_mul(0x0000_0100_0000_01b3)
+        })
+        .to_be_bytes()
+}
+
+impl Signer<ClusterConsensus> for ClusterSigner {
+    fn sign(&self, msg: &[u8]) -> Vec<u8> {
+        [&self.0[..], &fnv(msg)].concat()
+    }
+
+    fn verify(&self, public_key: &Vec<u8>, msg: &[u8], sign: &Vec<u8>) -> bool {
+        sign == &[&public_key[..], &fnv(msg)].concat()
+    }
+}
+
+pub struct ClusterApp {
+    pub epoch_id: u64,
+    pub proposed: u64,
+    /// Added to hash of proposed epoch, so each proposer produces a different hash.
+    pub salt: u64,
+    pub voters: Vec<Voter<Vec<u8>, Vec<u8>, u64>>,
+    pub committed: Arc<Mutex<Vec<u64>>>,
+    /// Committed epochs with hash.
+    pub hashes: Arc<Mutex<Vec<(u64, u64)>>>,
+}
+
+impl ClusterApp {
+    pub fn new(n: u8) -> Self {
+        Self {
+            epoch_id: 0,
+            proposed: 0,
+            salt: 0,
+            voters: voters(n),
+            committed: Arc::new(Mutex::new(Vec::new())),
+            hashes: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+}
+
+impl App<ClusterConsensus> for ClusterApp {
+    type Error = String;
+
+    type ProposeEpochFuture = Pin<Box<dyn Future<Output = Result<(u64, u64), String>> + Send>>;
+
+    type EnterStepFuture = Pin<Box<dyn Future<Output = Result<(u64, u64), Self::Error>> + Send>>;
+
+    type CommitFuture = Pin<
+        Box<dyn Future<Output = Result<Vec<Voter<Vec<u8>, Vec<u8>, u64>>, Self::Error>> + Send>,
+    >;
+
+    type ShutdownFuture = Pin<Box<dyn Future<Output = Result<(), Self::Error>> + Send>>;
+
+    fn propose_epoch(&mut self) -> Self::ProposeEpochFuture {
+        // Follow last proposed epoch on pipeline.
+        let epoch_id = self.epoch_id.max(self.proposed) + 1;
+        self.proposed = epoch_id;
+        let epoch_hash = epoch_id * 100 + self.salt;
+
+        Box::pin(async move { Ok((epoch_id, epoch_hash)) })
+    }
+
+    fn enter_step(&mut self, _step: u8, epoch_id: u64, epoch_hash: u64) -> Self::EnterStepFuture {
+        Box::pin(async move { Ok((epoch_id, epoch_hash)) })
+    }
+
+    fn commit(&mut self, epoch_id: &u64, epoch_hash: &u64) -> Self::CommitFuture {
+        self.epoch_id = *epoch_id;
+        self.committed.lock().unwrap().push(*epoch_id);
+        self.hashes.lock().unwrap().push((*epoch_id, *epoch_hash));
+
+        let voters = self.voters.clone();
+
+        Box::pin(async move { Ok(voters) })
+    }
+
+    fn shutdown(&mut self) -> Self::ShutdownFuture {
+        Box::pin(async move { Ok(()) })
+    }
+}
+
+/// Consensus with fixed proposer.
+pub struct ClusterConsensus {
+    pub n: u8,
+    pub proposer: Vec<u8>,
+    pub timeout: Duration,
+    pub timer: fn(Duration) -> BoxTimer,
+    pub pipelined: bool,
+    pub stable_leader: bool,
+    pub joint_consensus: bool,
+    pub quorum_grace: Duration,
+}
+
+impl ClusterConsensus {
+    pub fn new(n: u8, timeout: Duration, timer: fn(Duration) -> BoxTimer) -> Self {
+        Self {
+            n,
+            proposer: vec![1],
+            timeout,
+            timer,
+            pipelined: false,
+            stable_leader: false,
+            joint_consensus: false,
+            quorum_grace: Duration::ZERO,
+        }
+    }
+}
+
+impl Consensus for ClusterConsensus {
+    type Timer = BoxTimer;
+
+    type NodeId = Vec<u8>;
+
+    type Weight = u64;
+
+    type EpochId = u64;
+
+    type PublicKey = Vec<u8>;
+
+    type EpochHash = u64;
+
+    type Signature = Vec<u8>;
+
+    fn timer(&self, timeout: Duration) -> Self::Timer {
+        (self.timer)(timeout)
+    }
+
+    fn pipelined(&self) -> bool {
+        self.pipelined
+    }
+
+    fn stable_leader(&self) -> bool {
+        self.stable_leader
+    }
+
+    fn joint_consensus(&self) -> bool {
+        self.joint_consensus
+    }
+
+    fn timeout_policy(&self) -> TimeoutPolicy {
+        TimeoutPolicy::new(self.timeout).with_quorum_grace(self.quorum_grace)
+    }
+
+    type LatestEpochFuture = Pin<Box<dyn Future<Output = (u64, u64)> + Send>>;
+
+    fn latest_epoch(&self) -> Self::LatestEpochFuture {
+        Box::pin(async move { (0, 0) })
+    }
+
+    type LatestVoterSetFuture = Pin<
+        Box<dyn Future<Output = Vec<Voter<Self::NodeId, Self::PublicKey, Self::Weight>>> + Send>,
+    >;
+
+    fn latest_voter_set(&self) -> Self::LatestVoterSetFuture {
+        let r = voters(self.n);
+
+        Box::pin(async move { r })
+    }
+
+    type ComputeProposerFuture = Pin<Box<dyn Future<Output = Self::NodeId> + Send>>;
+
+    fn compute_proposer(&self, _epoch_hash: &Self::EpochHash) -> Self::ComputeProposerFuture {
+        let r = self.proposer.clone();
+
+        Box::pin(async move { r })
+    }
+}
+
+type Nodes = Vec<(Vec<u8>, Sender<Vec<u8>>)>;
+
+/// In-process transport, broadcast to all nodes.
+#[derive(Clone, Default)]
+pub struct Hub {
+    nodes: Arc<Mutex<Nodes>>,
+}
+
+impl Hub {
+    pub fn join(&self, node_id: u8) -> HubTransport {
+        let (sender, recver) = channel::unbounded();
+
+        self.nodes.lock().unwrap().push((vec![node_id], sender));
+
+        HubTransport {
+            hub: self.clone(),
+            recver,
+        }
+    }
+}
+
+pub struct HubTransport {
+    hub: Hub,
+    recver: Receiver<Vec<u8>>,
+}
+
+impl Transport<ClusterConsensus> for HubTransport {
+    type Error = RecvError;
+
+    fn send(&self, target: Option<Vec<u8>>, msg: Vec<u8>) {
+        for (node_id, sender) in self.hub.nodes.lock().unwrap().iter() {
+            if target.as_ref().is_none_or(|t| t == node_id) {
+                let _ = sender.try_send(msg.clone());
+            }
+        }
+    }
+
+    type RecvFuture = Pin<Box<dyn Future<Output = Result<Vec<u8>, RecvError>>>>;
+
+    fn recv(&self) -> Self::RecvFuture {
+        let recver = self.recver.clone();
+
+        Box::pin(async move { recver.recv().await })
+    }
+}
@@ -0,0 +1,84 @@
+use consensus_rs::{
+    codec::Codec,
+    packet::{Packet, Voted},
+    VoteSign,
+};
+
+type TestPacket = Packet<u64, [u8; 4], Vec<u8>>;
+
+#[test]
+fn packet_roundtrip() {
+    let packets: Vec<TestPacket> = vec![
+        Packet::broadcast_propose_from_id_hash(1, [1, 2, 3, 4], 0),
+        Packet::ResponsePropose(consensus_rs::packet::ResponsePropose {
+            epoch_id: 2,
+            epoch_hash: [5, 6, 7, 8],
+            round: 2,
+            vote_sign: Some(VoteSign {
+                idx: 3,
+                sign: vec![9, 9],
+            }),
+        }),
+        Packet::broadcast_commit_from_id_hash(
+            u64::MAX,
+            [0; 4],
+            vec![
+                VoteSign {
+                    idx: 0,
+                    sign: vec![],
+                },
+                VoteSign {
+                    idx: 1,
+                    sign: vec![1; 64],
+                },
+            ],
+        ),
+        Packet::RoundChange(consensus_rs::packet::RoundChange {
+            epoch_id: 3,
+            epoch_hash: [1; 4],
+            round: 7,
+            voted: Some(Voted {
+                epoch_id: 4,
+                epoch_hash: [3; 4],
+                round: 5,
+            }),
+            vote_sign: Some(VoteSign {
+                idx: 2,
+                sign: vec![4],
+            }),
+        }),
+        Packet::heartbeat_from_id_hash(4, [2; 4], 1, 9),
+        Packet::new_round_from_id_hash(
+            3,
+            [1; 4],
+            7,
+            None,
+            vec![VoteSign {
+                idx: 0,
+                sign: vec![5; 8],
+            }],
+        ),
+    ];
+
+    for pkt in packets {
+        let bytes = pkt.to_bytes();
+        let decoded = TestPacket::from_bytes(&bytes).unwrap();
+        assert_eq!(format!("{:?}", decoded), format!("{:?}", pkt));
+    }
+}
+
+#[test]
+fn reject_invalid_bytes() {
+    let bytes = TestPacket::broadcast_propose_from_id_hash(1, [1, 2, 3, 4], 0).to_bytes();
+
+    assert!(TestPacket::from_bytes(&bytes[..bytes.len() - 1]).is_none());
+    assert!(TestPacket::from_bytes(&[&bytes[..], &[0]].concat()).is_none());
+    assert!(TestPacket::from_bytes(&[3]).is_none());
+
+    // Length of vote signs is larger than input.
+    let mut bytes = vec![2];
+    1u64.encode(&mut bytes);
+    [0u8; 4].encode(&mut bytes);
+    u32::MAX.encode(&mut bytes);
+    assert!(TestPacket::from_bytes(&bytes).is_none());
+}
@@ -0,0 +1,198 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use cluster::ClusterConsensus;
+use consensus_rs::{
+    network::{GossipConfig, GossipTransport},
+    Transport,
+};
+use smol::{
+    channel::{self, Receiver, RecvError, Sender},
+    future,
+};
+
+mod cluster;
+
+type Nodes = Vec<(Vec<u8>, Sender<Vec<u8>>)>;
+
+/// In-process transport, only linked nodes are connected.
+#[derive(Clone, Default)]
+struct Hub {
+    nodes: Arc<Mutex<Nodes>>,
+}
+
+impl Hub {
+    fn join(&self, node_id: u8, links: &[u8]) -> LinkTransport {
+        let (sender, recver) = channel::unbounded();
+
+        self.nodes.lock().unwrap().push((vec![node_id], sender));
+
+        LinkTransport {
+            hub: self.clone(),
+            links: links.iter().map(|l| vec![*l]).collect(),
+            recver,
+            sent: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+}
+
+struct LinkTransport {
+    hub: Hub,
+    links: Vec<Vec<u8>>,
+    recver: Receiver<Vec<u8>>,
+    sent: Arc<AtomicUsize>,
+}
+
+impl Transport<ClusterConsensus> for LinkTransport {
+    type Error = RecvError;
+
+    fn send(&self, target: Option<Vec<u8>>, msg: Vec<u8>) {
+        for (node_id, sender) in self.hub.nodes.lock().unwrap().iter() {
+            if self.links.contains(node_id) && target.as_ref().is_none_or(|t| t == node_id) {
+                self.sent.fetch_add(1, Ordering::Relaxed);
+                let _ = sender.try_send(msg.clone());
+            }
+        }
+    }
+
+    type RecvFuture = Pin<Box<dyn Future<Output = Result<Vec<u8>, RecvError>>>>;
+
+    fn recv(&self) -> Self::RecvFuture {
+        let recver = self.recver.clone();
+
+        Box::pin(async move { recver.recv().await })
+    }
+}
+
+type Gossip = GossipTransport<ClusterConsensus, LinkTransport>;
+
+fn gossip(hub: &Hub, node_id: u8, links: &[u8], config: GossipConfig) -> Gossip {
+    let transport = hub.join(node_id, links);
+    let peers = links.iter().map(|l| vec![*l]).collect();
+
+    GossipTransport::new(vec![node_id], transport, peers, config)
+}
+
+/// Receive a message, `None` if nothing arrives in time.
+fn try_recv(gossip: &Gossip) -> Option<Vec<u8>> {
+    smol::block_on(future::or(
+        async { Some(gossip.recv().await.unwrap()) },
+        async {
+            smol::Timer::after(Duration::from_millis(50)).await;
+            None
+        },
+    ))
+}
+
+/// Nodes on a line: 1 - 2 - 3 - 4.
+fn line(hub: &Hub) -> Vec<Gossip> {
+    let config = GossipConfig::default().with_fanout(2);
+
+    vec![
+        gossip(hub, 1, &[2], config.clone()),
+        gossip(hub, 2, &[1, 3], config.clone()),
+        gossip(hub, 3, &[2, 4], config.clone()),
+        gossip(hub, 4, &[3], config),
+    ]
+}
+
+#[test]
+fn multi_hop_broadcast() {
+    let hub = Hub::default();
+    let nodes = line(&hub);
+
+    nodes[0].send(None, b"commit".to_vec());
+
+    for node in &nodes {
+        assert_eq!(try_recv(node), Some(b"commit".to_vec()));
+    }
+
+    // Relayed copies are suppressed.
+    for node in &nodes {
+        assert_eq!(try_recv(node), None);
+    }
+}
+
+#[test]
+fn multi_hop_unicast() {
+    let hub = Hub::default();
+    let nodes = line(&hub);
+
+    nodes[3].send(Some(vec![1]), b"vote".to_vec());
+
+    assert_eq!(try_recv(&nodes[3]), None);
+    assert_eq!(try_recv(&nodes[2]), None);
+    assert_eq!(try_recv(&nodes[1]), None);
+    assert_eq!(try_recv(&nodes[0]), Some(b"vote".to_vec()));
+}
+
+#[test]
+fn limit_fanout() {
+    let hub = Hub::default();
+
+    let config = GossipConfig::default().with_fanout(2);
+    let node = gossip(&hub, 1, &[2, 3, 4, 5], config);
+    let peers: Vec<Gossip> = (2..=5)
+        .map(|i| gossip(&hub, i, &[1], GossipConfig::default()))
+        .collect();
+
+    node.send(None, b"commit".to_vec());
+    assert_eq!(node.transport().sent.load(Ordering::Relaxed), 2);
+
+    let got = peers.iter().filter(|p| try_recv(p).is_some()).count();
+    assert_eq!(got, 2);
+}
+
+#[test]
+fn bounded_seen_cache() {
+    for (cache_size, expected) in [(4096, 2), (1, 3)] {
+        let hub = Hub::default();
+
+        let config = GossipConfig::default()
+            .with_ttl(0)
+            .with_cache_size(cache_size);
+        let node = gossip(&hub, 1, &[2], config);
+        let peer = gossip(&hub, 2, &[1], GossipConfig::default());
+
+        for msg in [b"a", b"b", b"a"] {
+            peer.send(Some(vec![1]), msg.to_vec());
+        }
+
+        let mut got = 0;
+        while try_recv(&node).is_some() {
+            got += 1;
+        }
+        assert_eq!(got, expected);
+    }
+}
+
+#[test]
+fn wake_on_local_message() {
+    let hub = Hub::default();
+    let nodes = line(&hub);
+
+    let (got, _) = smol::block_on(future::zip(
+        future::or(async { Some(nodes[0].recv().await.unwrap()) }, async {
+            smol::Timer::after(Duration::from_millis(200)).await;
+            None
+        }),
+        async {
+            // Recv is already waiting on transport.
+            smol::Timer::after(Duration::from_millis(20)).await;
+            nodes[0].send(Some(vec![1]), b"local".to_vec());
+        },
+    ));
+
+    assert_eq!(got, Some(b"local".to_vec()));
+
+    // Frame received in flight isn't lost.
+    nodes[1].send(Some(vec![1]), b"remote".to_vec());
+    assert_eq!(try_recv(&nodes[0]), Some(b"remote".to_vec()));
+}
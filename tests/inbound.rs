@@ -0,0 +1,299 @@
+use std::{
+    cell::Cell,
+    future::{pending, Future},
+    pin::Pin,
+    rc::Rc,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use cluster::{sign, timer, ClusterApp, ClusterConsensus};
+use consensus_rs::{
+    algorithm::BRaft,
+    event::EventKind,
+    packet::{Packet, ResponsePropose},
+    Network,
+};
+
+mod cluster;
+mod utils;
+
+type TestPacket = Packet<u64, u64, Vec<u8>>;
+
+/// Network flooding votes from a non-voter, a voter with bad index and a voter repeating its vote.
+struct FloodNetwork {
+    count: Rc<Cell<u64>>,
+}
+
+fn vote(idx: u64) -> TestPacket {
+    Packet::ResponsePropose(ResponsePropose {
+        epoch_id: 1,
+        epoch_hash: 100,
+        round: 0,
+        vote_sign: Some(sign(idx)),
+    })
+}
+
+impl Network<ClusterConsensus> for FloodNetwork {
+    type Error = ();
+
+    fn node_id(&self) -> Vec<u8> {
+        vec![1]
+    }
+
+    fn send_unsigned(&self, _target: Option<Vec<u8>>, _pkt: TestPacket) {}
+
+    type RecvFuture = Pin<Box<dyn Future<Output = Result<(TestPacket, Vec<u8>), ()>>>>;
+
+    fn recv(&self) -> Self::RecvFuture {
+        let i = self.count.get();
+        self.count.set(i + 1);
+
+        Box::pin(async move {
+            if i >= 150 {
+                return pending().await;
+            }
+
+            match i % 3 {
+                0 => Ok((vote(8), vec![9])),
+                1 => Ok((vote(0), vec![3])),
+                _ => Ok((vote(1), vec![2])),
+            }
+        })
+    }
+}
+
+/// Network of follower, delivers proposal of next epoch before commit of voted epoch.
+struct ReorderNetwork {
+    count: Rc<Cell<usize>>,
+    log: Log,
+}
+
+type Log = Arc<Mutex<Vec<(&'static str, u64)>>>;
+
+impl Network<ClusterConsensus> for ReorderNetwork {
+    type Error = ();
+
+    fn node_id(&self) -> Vec<u8> {
+        vec![2]
+    }
+
+    fn send_unsigned(&self, _target: Option<Vec<u8>>, pkt: TestPacket) {
+        if let Packet::ResponsePropose(p) = pkt {
+            self.log.lock().unwrap().push(("vote", p.epoch_id));
+        }
+    }
+
+    type RecvFuture = Pin<Box<dyn Future<Output = Result<(TestPacket, Vec<u8>), ()>>>>;
+
+    fn recv(&self) -> Self::RecvFuture {
+        let i = self.count.get();
+        self.count.set(i + 1);
+
+        let pkt = match i {
+            0 => Packet::broadcast_propose_from_id_hash(1, 100, 0),
+            1 => Packet::broadcast_propose_from_id_hash(2, 200, 0),
+            2 => Packet::broadcast_commit_from_id_hash(1, 100, vec![sign(0), sign(1)]),
+            _ => return Box::pin(pending()),
+        };
+
+        Box::pin(async move { Ok((pkt, vec![1])) })
+    }
+}
+
+/// Network of follower, delivers commit of epoch 2 before commit of epoch 1.
+struct GapNetwork {
+    count: Rc<Cell<usize>>,
+}
+
+impl Network<ClusterConsensus> for GapNetwork {
+    type Error = ();
+
+    fn node_id(&self) -> Vec<u8> {
+        vec![2]
+    }
+
+    fn send_unsigned(&self, _target: Option<Vec<u8>>, _pkt: TestPacket) {}
+
+    type RecvFuture = Pin<Box<dyn Future<Output = Result<(TestPacket, Vec<u8>), ()>>>>;
+
+    fn recv(&self) -> Self::RecvFuture {
+        let i = self.count.get();
+        self.count.set(i + 1);
+
+        let pkt = match i {
+            0 => Packet::broadcast_commit_from_id_hash(2, 200, vec![sign(0), sign(1)]),
+            1 => Packet::broadcast_commit_from_id_hash(1, 100, vec![sign(0), sign(1)]),
+            _ => return Box::pin(pending()),
+        };
+
+        Box::pin(async move { Ok((pkt, vec![1])) })
+    }
+}
+
+/// Network losing packet if receive is cancelled, packet arrives after first timeout.
+struct SlowNetwork {
+    count: Rc<Cell<usize>>,
+}
+
+impl Network<ClusterConsensus> for SlowNetwork {
+    type Error = ();
+
+    fn node_id(&self) -> Vec<u8> {
+        vec![2]
+    }
+
+    fn send_unsigned(&self, _target: Option<Vec<u8>>, _pkt: TestPacket) {}
+
+    type RecvFuture = Pin<Box<dyn Future<Output = Result<(TestPacket, Vec<u8>), ()>>>>;
+
+    fn recv(&self) -> Self::RecvFuture {
+        let i = self.count.get();
+        self.count.set(i + 1);
+
+        Box::pin(async move {
+            if i > 0 {
+                return pending().await;
+            }
+
+            smol::Timer::after(Duration::from_millis(80)).await;
+            Ok((
+                Packet::broadcast_commit_from_id_hash(1, 100, vec![sign(0), sign(1)]),
+                vec![1],
+            ))
+        })
+    }
+}
+
+#[test]
+fn drop_flooding_packets() {
+    utils::init();
+
+    smol::block_on(async {
+        let network = FloodNetwork {
+            count: Rc::new(Cell::new(0)),
+        };
+        let consensus = ClusterConsensus::new(3, Duration::from_millis(100), timer);
+        let app = ClusterApp::new(3);
+
+        let mut braft = BRaft::new(network, consensus, app).await.unwrap();
+
+        let dropped = Arc::new(Mutex::new(Vec::new()));
+        let counted = Arc::new(Mutex::new(Vec::new()));
+        {
+            let dropped = dropped.clone();
+            let counted = counted.clone();
+            braft.subscribe(move |e| match &e.kind {
+                EventKind::PacketDropped { sender, reason } => {
+                    dropped.lock().unwrap().push((sender[0], *reason))
+                }
+                EventKind::VoteCounted { idx, .. } => counted.lock().unwrap().push(*idx),
+                _ => {}
+            });
+        }
+
+        // Propose, then collect votes.
+        braft.do_tick().await.unwrap();
+        braft.do_tick().await.unwrap();
+
+        assert_eq!(*counted.lock().unwrap(), vec![1]);
+
+        let dropped = dropped.lock().unwrap();
+        let count = |sender: u8, reason: &str| {
+            dropped
+                .iter()
+                .filter(|d| d.0 == sender && d.1 == reason)
+                .count()
+        };
+
+        // Non-voter isn't tracked, each packet is dropped by check.
+        assert_eq!(count(9, "not_voter"), 50);
+        assert_eq!(count(9, "banned"), 0);
+        // Banned after 8 invalid packets.
+        assert_eq!(count(3, "bad_vote"), 8);
+        assert_eq!(count(3, "banned"), 42);
+        // Over budget of 32 packets on a round.
+        assert_eq!(count(2, "over_budget"), 8);
+        assert_eq!(count(2, "banned"), 10);
+    });
+}
+
+#[test]
+fn replay_future_packets() {
+    utils::init();
+
+    smol::block_on(async {
+        let log = Log::default();
+        let network = ReorderNetwork {
+            count: Rc::new(Cell::new(0)),
+            log: log.clone(),
+        };
+        let consensus = ClusterConsensus::new(3, Duration::from_millis(100), timer);
+        let app = ClusterApp::new(3);
+
+        let mut braft = BRaft::new(network, consensus, app).await.unwrap();
+
+        {
+            let log = log.clone();
+            braft.subscribe(move |e| {
+                if let EventKind::Committed { epoch_id, .. } = &e.kind {
+                    log.lock().unwrap().push(("commit", *epoch_id));
+                }
+            });
+        }
+
+        // Vote epoch 1, commit it, then vote buffered proposal of epoch 2.
+        braft.do_tick().await.unwrap();
+        braft.do_tick().await.unwrap();
+        braft.do_tick().await.unwrap();
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec![("vote", 1), ("commit", 1), ("vote", 2)]
+        );
+    });
+}
+
+#[test]
+fn buffer_commit_after_gap() {
+    utils::init();
+
+    smol::block_on(async {
+        let network = GapNetwork {
+            count: Rc::new(Cell::new(0)),
+        };
+        let consensus = ClusterConsensus::new(3, Duration::from_millis(100), timer);
+        let app = ClusterApp::new(3);
+        let committed = app.committed.clone();
+
+        let mut braft = BRaft::new(network, consensus, app).await.unwrap();
+
+        // Commit of epoch 2 is buffered until epoch 1 committed.
+        braft.do_tick().await.unwrap();
+        braft.do_tick().await.unwrap();
+
+        assert_eq!(*committed.lock().unwrap(), vec![1, 2]);
+    });
+}
+
+#[test]
+fn keep_receive_across_ticks() {
+    utils::init();
+
+    smol::block_on(async {
+        let network = SlowNetwork {
+            count: Rc::new(Cell::new(0)),
+        };
+        let consensus = ClusterConsensus::new(3, Duration::from_millis(50), timer);
+        let app = ClusterApp::new(3);
+
+        let mut braft = BRaft::new(network, consensus, app).await.unwrap();
+
+        // Timeout, then the same receive gets the commit.
+        braft.do_tick().await.unwrap();
+        assert_eq!(braft.status().latest_epoch_id, 0);
+
+        braft.do_tick().await.unwrap();
+        assert_eq!(braft.status().latest_epoch_id, 1);
+    });
+}
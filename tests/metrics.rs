@@ -0,0 +1,78 @@
+#![cfg(feature = "metrics")]
+
+use braft_test::{SingleApp, SingleConsensus, SingleNetwork};
+use consensus_rs::{
+    algorithm::BRaft,
+    event::{Event, EventKind},
+    metrics::Metrics,
+};
+
+mod braft_test;
+mod utils;
+
+#[test]
+fn single_node_metrics() {
+    utils::init();
+
+    let network = SingleNetwork::new();
+    let app = SingleApp::new();
+    let consensus = SingleConsensus::new(vec![1]);
+
+    let braft = BRaft::new(network, consensus, app);
+
+    smol::block_on(async move {
+        let mut braft = braft.await.unwrap();
+
+        let metrics = Metrics::new();
+        braft.subscribe(metrics.listener::<SingleConsensus>());
+
+        for _ in 0..4 {
+            braft.do_tick().await.unwrap();
+        }
+
+        let text = metrics.render();
+
+        assert!(text.contains("consensus_commits_total 2\n"));
+        assert!(text.contains("consensus_commit_latency_seconds_count 2\n"));
+        assert!(text.contains("consensus_votes_counted_total 2\n"));
+        // Commit on quorum without timeout.
+        assert!(!text.contains("consensus_timeouts_total{role=\"proposer\",step=\"1\"}"));
+        assert!(text.contains("consensus_packets_received_total{packet=\"broadcast_propose\"} 2\n"));
+        assert!(text.contains("consensus_quorum_weight 1\n"));
+    })
+}
+
+#[test]
+fn pipelined_commit_latency() {
+    let metrics = Metrics::new();
+
+    let record = |kind| {
+        metrics.record::<SingleConsensus>(&Event {
+            epoch_id: 0,
+            round: 0,
+            step: 0,
+            kind,
+        })
+    };
+
+    // Epoch 2 is proposed while epoch 1 is pending.
+    record(EventKind::Proposed {
+        epoch_id: 1,
+        epoch_hash: 1,
+    });
+    record(EventKind::Proposed {
+        epoch_id: 2,
+        epoch_hash: 2,
+    });
+    record(EventKind::Committed {
+        epoch_id: 1,
+        epoch_hash: 1,
+    });
+    record(EventKind::Committed {
+        epoch_id: 2,
+        epoch_hash: 2,
+    });
+
+    let text = metrics.render();
+    assert!(text.contains("consensus_commit_latency_seconds_count 2\n"));
+}
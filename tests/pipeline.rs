@@ -0,0 +1,79 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use cluster::{timer, ClusterApp, ClusterConsensus, ClusterSigner, Hub};
+use consensus_rs::{algorithm::BRaft, event::EventKind, network::SignedNetwork};
+use smol::LocalExecutor;
+
+mod cluster;
+mod utils;
+
+#[test]
+fn propose_before_commit() {
+    utils::init();
+
+    let executor = LocalExecutor::new();
+
+    smol::block_on(executor.run(async {
+        let hub = Hub::default();
+
+        let mut handles = Vec::new();
+        let mut tasks = Vec::new();
+        let mut committed = Vec::new();
+        let events = Arc::new(Mutex::new(Vec::new()));
+
+        for i in 1..=3u8 {
+            let network = SignedNetwork::new(vec![i], hub.join(i), ClusterSigner(vec![i]));
+            let mut consensus = ClusterConsensus::new(3, Duration::from_millis(100), timer);
+            consensus.pipelined = true;
+            let app = ClusterApp::new(3);
+            committed.push(app.committed.clone());
+
+            let mut braft = BRaft::new(network, consensus, app).await.unwrap();
+
+            if i == 1 {
+                let events = events.clone();
+                braft.subscribe(move |e| match &e.kind {
+                    EventKind::Proposed { epoch_id, .. } => {
+                        events.lock().unwrap().push(("propose", *epoch_id))
+                    }
+                    EventKind::Committed { epoch_id, .. } => {
+                        events.lock().unwrap().push(("commit", *epoch_id))
+                    }
+                    _ => {}
+                });
+            }
+
+            handles.push(braft.control_handle());
+            tasks.push(executor.spawn(async move { braft.run().await }));
+        }
+
+        smol::Timer::after(Duration::from_millis(500)).await;
+
+        for handle in &handles {
+            handle.shutdown();
+        }
+
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        let proposer = committed[0].lock().unwrap().clone();
+
+        // Quorum doesn't wait timer on pipeline, commits are in order.
+        for c in &committed {
+            let c = c.lock().unwrap();
+            assert!(c.len() >= 10);
+            assert!(c.windows(2).all(|w| w[0] < w[1]));
+            assert!(proposer.starts_with(&c[..c.len().min(proposer.len())]));
+        }
+
+        // Next epoch is proposed before previous epoch is committed.
+        let events = events.lock().unwrap();
+        let propose = events.iter().position(|e| e == &("propose", 2)).unwrap();
+        let commit = events.iter().position(|e| e == &("commit", 1)).unwrap();
+        assert!(propose < commit);
+    }));
+}
@@ -0,0 +1,52 @@
+use consensus_rs::{
+    quorum::{FaultTolerance, QuorumCollector},
+    Voter, VoterConfig, VoterSet,
+};
+
+fn voter_config(weights: &[u64]) -> VoterConfig<Vec<u8>, Vec<u8>, u64> {
+    let voters = weights
+        .iter()
+        .enumerate()
+        .map(|(i, w)| Voter {
+            voter_id: vec![i as u8],
+            public_key: vec![i as u8],
+            weight: *w,
+        })
+        .collect();
+
+    VoterConfig::Single(VoterSet::new(voters).unwrap())
+}
+
+#[test]
+fn fault_tolerance_threshold() {
+    assert!(!FaultTolerance::Cft.is_quorum(&2u64, &4));
+    assert!(FaultTolerance::Cft.is_quorum(&3u64, &4));
+
+    assert!(!FaultTolerance::Bft.is_quorum(&2u64, &3));
+    assert!(FaultTolerance::Bft.is_quorum(&3u64, &4));
+    assert!(!FaultTolerance::Bft.is_quorum(&6u64, &9));
+    assert!(FaultTolerance::Bft.is_quorum(&7u64, &9));
+}
+
+#[test]
+fn collector_dedup_and_quorum() {
+    let config = voter_config(&[1, 1, 2]);
+
+    let mut collector = QuorumCollector::<u64, u64>::new(FaultTolerance::Cft);
+
+    assert!(collector.add_vote(&1, 0, &10, 0));
+    assert!(!collector.add_vote(&1, 0, &10, 0));
+    assert!(!collector.has_quorum(&1, 0, &10, &config));
+    assert_eq!(collector.weight(&1, 0, &10, &config), 1);
+
+    // Vote on other hash or round is counted separately.
+    assert!(collector.add_vote(&1, 0, &11, 2));
+    assert!(collector.add_vote(&1, 1, &10, 2));
+    assert!(!collector.has_quorum(&1, 0, &10, &config));
+
+    assert!(collector.add_vote(&1, 0, &10, 2));
+    assert!(collector.has_quorum(&1, 0, &10, &config));
+
+    collector.prune(&1);
+    assert!(collector.votes(&1, 0, &10).is_none());
+}
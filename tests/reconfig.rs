@@ -0,0 +1,184 @@
+use std::{
+    cell::Cell,
+    future::{pending, Future},
+    pin::Pin,
+    rc::Rc,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use cluster::{sign, timer, voters, ClusterApp, ClusterConsensus, ClusterSigner, Hub};
+use consensus_rs::{
+    algorithm::BRaft, event::EventKind, network::SignedNetwork, packet::Packet, Network, Role,
+    Voter,
+};
+use smol::LocalExecutor;
+
+mod cluster;
+mod utils;
+
+type TestPacket = Packet<u64, u64, Vec<u8>>;
+
+/// Network of follower, delivers commit of epoch 1.
+struct CommitNetwork {
+    count: Rc<Cell<usize>>,
+}
+
+impl Network<ClusterConsensus> for CommitNetwork {
+    type Error = ();
+
+    fn node_id(&self) -> Vec<u8> {
+        vec![2]
+    }
+
+    fn send_unsigned(&self, _target: Option<Vec<u8>>, _pkt: TestPacket) {}
+
+    type RecvFuture = Pin<Box<dyn Future<Output = Result<(TestPacket, Vec<u8>), ()>>>>;
+
+    fn recv(&self) -> Self::RecvFuture {
+        let i = self.count.get();
+        self.count.set(i + 1);
+
+        if i > 0 {
+            return Box::pin(pending());
+        }
+
+        let pkt = Packet::broadcast_commit_from_id_hash(1, 100, vec![sign(0), sign(2)]);
+
+        Box::pin(async move { Ok((pkt, vec![1])) })
+    }
+}
+
+/// Commit epoch 1 on follower, app returns `next` as voter set of next epoch.
+async fn commit_with(
+    next: Vec<Voter<Vec<u8>, Vec<u8>, u64>>,
+) -> (
+    BRaft<CommitNetwork, ClusterApp, ClusterConsensus>,
+    Vec<EventKind<Vec<u8>, u64, u64, u64>>,
+) {
+    let network = CommitNetwork {
+        count: Rc::new(Cell::new(0)),
+    };
+    let consensus = ClusterConsensus::new(3, Duration::from_millis(100), timer);
+    let mut app = ClusterApp::new(3);
+    app.voters = next;
+
+    let mut braft = BRaft::new(network, consensus, app).await.unwrap();
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    {
+        let events = events.clone();
+        braft.subscribe(move |e| events.lock().unwrap().push(e.kind.clone()));
+    }
+
+    braft.do_tick().await.unwrap();
+    assert_eq!(braft.status().latest_epoch_id, 1);
+
+    let events = events.lock().unwrap().clone();
+
+    (braft, events)
+}
+
+#[test]
+fn keep_voter_set_on_invalid_commit() {
+    utils::init();
+
+    let mut duplicate = voters(3);
+    duplicate.push(duplicate[0].clone());
+
+    let mut zero_weight = voters(3);
+    zero_weight[2].weight = 0;
+
+    for next in [vec![], duplicate, zero_weight] {
+        smol::block_on(async {
+            let (braft, events) = commit_with(next).await;
+
+            let status = braft.status();
+            assert_eq!(status.voters.latest().len(), 3);
+            assert!(!status.voters.is_joint());
+            assert_eq!(status.role, Role::Follower);
+            assert!(!events
+                .iter()
+                .any(|e| matches!(e, EventKind::VoterSetChanged { .. })));
+        });
+    }
+}
+
+#[test]
+fn removed_voter_becomes_observer() {
+    utils::init();
+
+    smol::block_on(async {
+        let next = voters(3)
+            .into_iter()
+            .filter(|v| v.voter_id != vec![2])
+            .collect();
+
+        let (braft, events) = commit_with(next).await;
+
+        let status = braft.status();
+        assert_eq!(status.voters.latest().len(), 2);
+        assert_eq!(status.role, Role::Observer);
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, EventKind::VoterSetChanged { joint: false })));
+        assert!(events.iter().any(|e| matches!(
+            e,
+            EventKind::RoleChanged {
+                from: Role::Follower,
+                to: Role::Observer
+            }
+        )));
+    });
+}
+
+#[test]
+fn transition_epoch_needs_both_sets() {
+    utils::init();
+
+    let executor = LocalExecutor::new();
+
+    smol::block_on(executor.run(async {
+        let hub = Hub::default();
+
+        // Voter set changes from 1, 2, 3 to 1, 4, 5 on epoch 1, new voters never run.
+        let next: Vec<_> = voters(5)
+            .into_iter()
+            .filter(|v| [1, 4, 5].contains(&v.voter_id[0]))
+            .collect();
+
+        let mut handles = Vec::new();
+        let mut tasks = Vec::new();
+        let mut committed = Vec::new();
+
+        for i in 1..=3u8 {
+            let network = SignedNetwork::new(vec![i], hub.join(i), ClusterSigner(vec![i]));
+            let mut consensus = ClusterConsensus::new(3, Duration::from_millis(100), timer);
+            consensus.joint_consensus = true;
+            let mut app = ClusterApp::new(3);
+            app.voters = next.clone();
+            committed.push(app.committed.clone());
+
+            let mut braft = BRaft::new(network, consensus, app).await.unwrap();
+            handles.push(braft.control_handle());
+            tasks.push(executor.spawn(async move { braft.run().await }));
+        }
+
+        smol::Timer::after(Duration::from_millis(1000)).await;
+
+        let status = handles[0].dump_status().await.unwrap();
+        assert!(status.voters.is_joint());
+
+        for handle in &handles {
+            handle.shutdown();
+        }
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        // Quorum of old set alone doesn't commit transition epoch.
+        for c in &committed {
+            assert_eq!(*c.lock().unwrap(), vec![1]);
+        }
+    }));
+}
@@ -0,0 +1,194 @@
+use std::{
+    future::{pending, Future},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use cluster::{timer, ClusterApp, ClusterConsensus, ClusterSigner, Hub};
+use consensus_rs::{algorithm::BRaft, event::EventKind, network::SignedNetwork, Transport};
+use smol::LocalExecutor;
+
+mod cluster;
+mod utils;
+
+/// Transport without loop back, nothing is received.
+struct Silent;
+
+impl Transport<ClusterConsensus> for Silent {
+    type Error = ();
+
+    fn send(&self, _target: Option<Vec<u8>>, _msg: Vec<u8>) {}
+
+    type RecvFuture = Pin<Box<dyn Future<Output = Result<Vec<u8>, ()>>>>;
+
+    fn recv(&self) -> Self::RecvFuture {
+        Box::pin(pending())
+    }
+}
+
+type Log = Arc<Mutex<Vec<(&'static str, u64)>>>;
+
+/// Run 3 nodes with long step timeout, return events of proposer.
+async fn run(executor: &LocalExecutor<'_>, quorum_grace: Duration) -> Log {
+    let hub = Hub::default();
+    let log = Log::default();
+
+    let mut handles = Vec::new();
+    let mut tasks = Vec::new();
+
+    for i in 1..=3u8 {
+        let network = SignedNetwork::new(vec![i], hub.join(i), ClusterSigner(vec![i]));
+        let mut consensus = ClusterConsensus::new(3, Duration::from_secs(1), timer);
+        consensus.quorum_grace = quorum_grace;
+        let app = ClusterApp::new(3);
+
+        let mut braft = BRaft::new(network, consensus, app).await.unwrap();
+
+        if i == 1 {
+            let log = log.clone();
+            braft.subscribe(move |e| match &e.kind {
+                EventKind::VoteCounted { idx, .. } => log.lock().unwrap().push(("vote", *idx)),
+                EventKind::Committed { epoch_id, .. } => {
+                    log.lock().unwrap().push(("commit", *epoch_id))
+                }
+                EventKind::Timeout { .. } => log.lock().unwrap().push(("timeout", 0)),
+                _ => {}
+            });
+        }
+
+        handles.push(braft.control_handle());
+        tasks.push(executor.spawn(async move { braft.run().await }));
+    }
+
+    smol::Timer::after(Duration::from_millis(300)).await;
+
+    for handle in &handles {
+        handle.shutdown();
+    }
+
+    for task in tasks {
+        task.await.unwrap();
+    }
+
+    log
+}
+
+/// Commits before first timeout.
+///
+/// Proposer times out after followers are shut down.
+fn commits(log: &Log) -> usize {
+    log.lock()
+        .unwrap()
+        .iter()
+        .take_while(|e| e.0 != "timeout")
+        .filter(|e| e.0 == "commit")
+        .count()
+}
+
+/// Votes counted before first commit.
+fn votes(log: &Log) -> usize {
+    log.lock()
+        .unwrap()
+        .iter()
+        .take_while(|e| e.0 != "commit")
+        .filter(|e| e.0 == "vote")
+        .count()
+}
+
+#[test]
+fn commit_on_quorum() {
+    utils::init();
+
+    let executor = LocalExecutor::new();
+
+    smol::block_on(executor.run(async {
+        let log = run(&executor, Duration::ZERO).await;
+
+        // Commit without waiting step timer.
+        assert!(commits(&log) >= 3);
+        assert_eq!(votes(&log), 2);
+    }));
+}
+
+#[test]
+fn collect_votes_on_grace() {
+    utils::init();
+
+    let executor = LocalExecutor::new();
+
+    smol::block_on(executor.run(async {
+        let log = run(&executor, Duration::from_millis(50)).await;
+
+        // All voters signed, commit before grace ends.
+        assert!(commits(&log) >= 3);
+        assert_eq!(votes(&log), 3);
+    }));
+}
+
+#[test]
+fn no_commit_without_quorum() {
+    utils::init();
+
+    smol::block_on(async {
+        // Only proposer is running, its own vote isn't a quorum.
+        let hub = Hub::default();
+        let network = SignedNetwork::new(vec![1], hub.join(1), ClusterSigner(vec![1]));
+        let consensus = ClusterConsensus::new(3, Duration::from_millis(50), timer);
+        let app = ClusterApp::new(3);
+        let committed = app.committed.clone();
+
+        let mut braft = BRaft::new(network, consensus, app).await.unwrap();
+
+        let proposed = Arc::new(Mutex::new(Vec::new()));
+        {
+            let proposed = proposed.clone();
+            braft.subscribe(move |e| {
+                if let EventKind::Proposed { epoch_id, .. } = &e.kind {
+                    proposed.lock().unwrap().push(*epoch_id);
+                }
+            });
+        }
+
+        for _ in 0..4 {
+            braft.do_tick().await.unwrap();
+        }
+
+        // Proposal is abandoned, nothing is proposed again without a certified round.
+        assert!(committed.lock().unwrap().is_empty());
+        assert_eq!(proposed.lock().unwrap().len(), 1);
+
+        let status = braft.status();
+        assert_eq!(status.latest_epoch_id, 0);
+        assert_eq!(status.epoch_id, 0);
+        assert_eq!(status.round, 3);
+        assert_eq!(status.step, 0);
+    });
+}
+
+#[test]
+fn count_own_vote() {
+    utils::init();
+
+    smol::block_on(async {
+        let network = SignedNetwork::new(vec![1], Silent, ClusterSigner(vec![1]));
+        let consensus = ClusterConsensus::new(1, Duration::from_millis(50), timer);
+        let app = ClusterApp::new(1);
+        let committed = app.committed.clone();
+
+        let mut braft = BRaft::new(network, consensus, app).await.unwrap();
+
+        braft.do_tick().await.unwrap();
+
+        let status = braft.status();
+        assert_eq!(status.weight, 1);
+        assert_eq!(status.vote_signers, vec![vec![1]]);
+
+        for _ in 0..3 {
+            braft.do_tick().await.unwrap();
+        }
+
+        // Quorum of single voter without broadcast looped back.
+        assert_eq!(*committed.lock().unwrap(), vec![1, 2]);
+    });
+}
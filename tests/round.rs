@@ -0,0 +1,462 @@
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    future::{pending, Future},
+    pin::Pin,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use cluster::{sign, timer, ClusterApp, ClusterConsensus, ClusterSigner, Hub, HubTransport};
+use consensus_rs::{
+    algorithm::BRaft,
+    event::EventKind,
+    network::SignedNetwork,
+    packet::{NewRound, Packet, ResponsePropose, RoundChange},
+    Network, Role, VoteSign, VoterConfig,
+};
+use smol::LocalExecutor;
+
+mod cluster;
+mod utils;
+
+type TestPacket = Packet<u64, u64, Vec<u8>>;
+
+type Log = Rc<RefCell<Vec<(&'static str, u64, usize)>>>;
+
+/// Network of follower, delivers scripted packets and logs round packets sent.
+struct ScriptNetwork {
+    script: RefCell<VecDeque<(TestPacket, Vec<u8>)>>,
+    sent: Log,
+}
+
+impl Network<ClusterConsensus> for ScriptNetwork {
+    type Error = ();
+
+    fn node_id(&self) -> Vec<u8> {
+        vec![2]
+    }
+
+    fn send_unsigned(&self, _target: Option<Vec<u8>>, pkt: TestPacket) {
+        match pkt {
+            Packet::ResponsePropose(p) => self.sent.borrow_mut().push(("vote", p.round, 0)),
+            Packet::RoundChange(p) => self.sent.borrow_mut().push(("round_change", p.round, 0)),
+            Packet::NewRound(p) => {
+                self.sent
+                    .borrow_mut()
+                    .push(("new_round", p.round, p.vote_signs.len()))
+            }
+            _ => {}
+        }
+    }
+
+    type RecvFuture = Pin<Box<dyn Future<Output = Result<(TestPacket, Vec<u8>), ()>>>>;
+
+    fn recv(&self) -> Self::RecvFuture {
+        match self.script.borrow_mut().pop_front() {
+            Some(r) => Box::pin(async move { Ok(r) }),
+            None => Box::pin(pending()),
+        }
+    }
+}
+
+type ClusterNetwork = SignedNetwork<ClusterConsensus, HubTransport, ClusterSigner>;
+
+/// Network losing commits of this node if `lossy`, and all packets once `down` is set.
+struct LossyNetwork {
+    inner: ClusterNetwork,
+    lossy: bool,
+    down: Arc<AtomicBool>,
+}
+
+impl Network<ClusterConsensus> for LossyNetwork {
+    type Error = <ClusterNetwork as Network<ClusterConsensus>>::Error;
+
+    fn node_id(&self) -> Vec<u8> {
+        self.inner.node_id()
+    }
+
+    fn send_unsigned(&self, target: Option<Vec<u8>>, pkt: TestPacket) {
+        let lost = self.lossy && matches!(pkt, Packet::BroadcastCommit(_));
+
+        if !lost && !self.down.load(Ordering::Relaxed) {
+            self.inner.send_unsigned(target, pkt);
+        }
+    }
+
+    type RecvFuture = <ClusterNetwork as Network<ClusterConsensus>>::RecvFuture;
+
+    fn recv(&self) -> Self::RecvFuture {
+        self.inner.recv()
+    }
+
+    fn sign_vote(&self, epoch_id: &u64, epoch_hash: &u64) -> Option<VoteSign<Vec<u8>>> {
+        self.inner.sign_vote(epoch_id, epoch_hash)
+    }
+
+    fn verify_votes(&self, pkt: &TestPacket) -> bool {
+        self.inner.verify_votes(pkt)
+    }
+
+    fn update_voters(&mut self, voters: &VoterConfig<Vec<u8>, Vec<u8>, u64>) {
+        self.inner.update_voters(voters)
+    }
+}
+
+fn round_change(round: u64, idx: u64) -> TestPacket {
+    Packet::RoundChange(RoundChange {
+        epoch_id: 0,
+        epoch_hash: 0,
+        round,
+        voted: None,
+        vote_sign: Some(sign(idx)),
+    })
+}
+
+fn new_round(round: u64, idxs: &[u64]) -> TestPacket {
+    Packet::NewRound(NewRound {
+        epoch_id: 0,
+        epoch_hash: 0,
+        round,
+        voted: None,
+        vote_signs: idxs.iter().map(|idx| sign(*idx)).collect(),
+    })
+}
+
+async fn node(
+    proposer: Vec<u8>,
+    script: Vec<(TestPacket, Vec<u8>)>,
+) -> (
+    BRaft<ScriptNetwork, ClusterApp, ClusterConsensus>,
+    Log,
+    Arc<Mutex<Vec<u64>>>,
+) {
+    let sent = Log::default();
+    let network = ScriptNetwork {
+        script: RefCell::new(script.into()),
+        sent: sent.clone(),
+    };
+    let mut consensus = ClusterConsensus::new(3, Duration::from_millis(100), timer);
+    consensus.proposer = proposer;
+    let app = ClusterApp::new(3);
+
+    let mut braft = BRaft::new(network, consensus, app).await.unwrap();
+
+    let certified = Arc::new(Mutex::new(Vec::new()));
+    {
+        let certified = certified.clone();
+        braft.subscribe(move |e| {
+            if let EventKind::RoundCertified { round } = e.kind {
+                certified.lock().unwrap().push(round);
+            }
+        });
+    }
+
+    (braft, sent, certified)
+}
+
+async fn follower(
+    script: Vec<(TestPacket, Vec<u8>)>,
+) -> (
+    BRaft<ScriptNetwork, ClusterApp, ClusterConsensus>,
+    Log,
+    Arc<Mutex<Vec<u64>>>,
+) {
+    node(vec![1], script).await
+}
+
+#[test]
+fn broadcast_round_change_on_timeout() {
+    utils::init();
+
+    smol::block_on(async {
+        let (mut braft, sent, _) = follower(vec![]).await;
+
+        braft.do_tick().await.unwrap();
+
+        assert_eq!(braft.status().round, 1);
+        assert_eq!(*sent.borrow(), vec![("round_change", 1, 0)]);
+    });
+}
+
+#[test]
+fn certify_round_on_quorum() {
+    utils::init();
+
+    smol::block_on(async {
+        let (mut braft, sent, certified) = follower(vec![
+            (round_change(2, 0), vec![1]),
+            (round_change(2, 0), vec![1]),
+            (round_change(2, 2), vec![3]),
+        ])
+        .await;
+
+        braft.do_tick().await.unwrap();
+
+        // Duplicate vote isn't counted, certificate has a quorum of votes.
+        assert_eq!(*sent.borrow(), vec![("new_round", 2, 2)]);
+        assert_eq!(*certified.lock().unwrap(), vec![2]);
+
+        let status = braft.status();
+        assert_eq!(status.round, 2);
+        assert_eq!(status.step, 0);
+    });
+}
+
+#[test]
+fn ignore_far_round() {
+    utils::init();
+
+    smol::block_on(async {
+        let (mut braft, sent, certified) = follower(vec![
+            (round_change(40, 0), vec![1]),
+            (round_change(40, 2), vec![3]),
+        ])
+        .await;
+
+        braft.do_tick().await.unwrap();
+
+        // Votes beyond round window are dropped, node times out on its own round.
+        assert!(certified.lock().unwrap().is_empty());
+        assert_eq!(*sent.borrow(), vec![("round_change", 1, 0)]);
+    });
+}
+
+#[test]
+fn follow_new_round() {
+    utils::init();
+
+    smol::block_on(async {
+        let (mut braft, sent, certified) = follower(vec![
+            // Without quorum, dropped by inbound check.
+            (new_round(9, &[0]), vec![1]),
+            (new_round(5, &[0, 2]), vec![3]),
+            // Lower round is ignored.
+            (new_round(3, &[0, 2]), vec![3]),
+        ])
+        .await;
+
+        braft.do_tick().await.unwrap();
+
+        assert_eq!(braft.status().round, 5);
+        assert_eq!(*certified.lock().unwrap(), vec![5]);
+        // Certificate isn't broadcast again.
+        assert!(sent.borrow().is_empty());
+    });
+}
+
+#[test]
+fn vote_only_for_proposer() {
+    utils::init();
+
+    smol::block_on(async {
+        let propose =
+            |epoch_hash, round| Packet::broadcast_propose_from_id_hash(1, epoch_hash, round);
+        let (mut braft, sent, _) = follower(vec![
+            // Not proposer of this round.
+            (propose(100, 0), vec![3]),
+            // Proposer, but of later round not certified.
+            (propose(200, 1), vec![1]),
+            (propose(300, 0), vec![1]),
+        ])
+        .await;
+
+        braft.do_tick().await.unwrap();
+        braft.do_tick().await.unwrap();
+
+        assert_eq!(*sent.borrow(), vec![("vote", 0, 0)]);
+        assert_eq!(braft.status().step, 1);
+    });
+}
+
+#[test]
+fn ignore_duplicate_vote_on_proposal() {
+    utils::init();
+
+    smol::block_on(async {
+        let vote = Packet::ResponsePropose(ResponsePropose {
+            epoch_id: 1,
+            epoch_hash: 100,
+            round: 0,
+            vote_sign: Some(sign(2)),
+        });
+        let (mut braft, _, _) = node(vec![2], vec![(vote.clone(), vec![3]), (vote, vec![3])]).await;
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        {
+            let events = events.clone();
+            braft.subscribe(move |e| events.lock().unwrap().push(e.kind.clone()));
+        }
+
+        // Propose, then collect votes until timeout.
+        braft.do_tick().await.unwrap();
+        braft.do_tick().await.unwrap();
+
+        // Same voter counted once, 1 of 3 isn't a quorum.
+        let events = events.lock().unwrap();
+        let counted = events
+            .iter()
+            .filter(|e| matches!(e, EventKind::VoteCounted { idx: 2, .. }))
+            .count();
+        let duplicates = events
+            .iter()
+            .filter(|e| matches!(e, EventKind::DuplicateVote { idx: 2 }))
+            .count();
+        assert_eq!((counted, duplicates), (1, 1));
+        assert!(!events.iter().any(|e| matches!(
+            e,
+            EventKind::QuorumReached { .. } | EventKind::Committed { .. }
+        )));
+        assert_eq!(braft.status().latest_epoch_id, 0);
+    });
+}
+
+#[test]
+fn step_down_to_next_voter() {
+    utils::init();
+
+    smol::block_on(async {
+        let (mut braft, sent, certified) = node(
+            vec![2],
+            vec![(round_change(1, 0), vec![1]), (round_change(1, 2), vec![3])],
+        )
+        .await;
+        assert_eq!(braft.status().role, Role::Proposer);
+
+        let handle = braft.control_handle();
+        handle.step_down();
+        let (status, res) = futures_lite::future::zip(handle.dump_status(), braft.do_tick()).await;
+        res.unwrap();
+
+        // Proposer is kept until round change is certified.
+        let status = status.unwrap();
+        assert_eq!(status.round, 1);
+        assert_eq!(status.role, Role::Proposer);
+        assert_eq!(status.proposer, vec![2]);
+        assert_eq!(sent.borrow()[0], ("round_change", 1, 0));
+
+        let status = braft.status();
+        assert_eq!(*certified.lock().unwrap(), vec![1]);
+        assert_eq!(status.role, Role::Follower);
+        assert_eq!(status.proposer, vec![3]);
+        assert_eq!(status.step, 0);
+    });
+}
+
+#[test]
+fn elect_on_silent_proposer() {
+    utils::init();
+
+    let executor = LocalExecutor::new();
+
+    smol::block_on(executor.run(async {
+        let hub = Hub::default();
+        // Proposer computed from epoch hash never runs.
+        let _silent = hub.join(1);
+
+        let mut handles = Vec::new();
+        let mut tasks = Vec::new();
+        let mut committed = Vec::new();
+
+        for i in 2..=3u8 {
+            let network = SignedNetwork::new(vec![i], hub.join(i), ClusterSigner(vec![i]));
+            let consensus = ClusterConsensus::new(3, Duration::from_millis(100), timer);
+            let app = ClusterApp::new(3);
+            committed.push(app.committed.clone());
+
+            let mut braft = BRaft::new(network, consensus, app).await.unwrap();
+            handles.push(braft.control_handle());
+            tasks.push(executor.spawn(async move { braft.run().await }));
+        }
+
+        smol::Timer::after(Duration::from_millis(1000)).await;
+
+        for handle in &handles {
+            handle.shutdown();
+        }
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        // Proposer of certified round 1 commits, proposer is computed again on each epoch.
+        for c in &committed {
+            assert!(!c.lock().unwrap().is_empty());
+        }
+    }));
+}
+
+#[test]
+fn repropose_locked_epoch() {
+    utils::init();
+
+    let executor = LocalExecutor::new();
+
+    smol::block_on(executor.run(async {
+        let hub = Hub::default();
+
+        let mut handles = Vec::new();
+        let mut tasks = Vec::new();
+        let mut hashes = Vec::new();
+
+        for i in 1..=3u8 {
+            let inner = SignedNetwork::new(vec![i], hub.join(i), ClusterSigner(vec![i]));
+            let consensus = ClusterConsensus::new(3, Duration::from_millis(100), timer);
+            // Each proposer produces a different hash of same epoch.
+            let mut app = ClusterApp::new(3);
+            app.salt = i as u64;
+            hashes.push(app.hashes.clone());
+
+            let down = Arc::new(AtomicBool::new(false));
+            let network = LossyNetwork {
+                inner,
+                lossy: i == 1,
+                down: down.clone(),
+            };
+
+            let mut braft = BRaft::new(network, consensus, app).await.unwrap();
+
+            // Proposer commits first epoch, its commit is lost, then it goes down.
+            if i == 1 {
+                braft.subscribe(move |e| {
+                    if let EventKind::Committed { .. } = e.kind {
+                        down.store(true, Ordering::Relaxed);
+                    }
+                });
+            }
+
+            handles.push(braft.control_handle());
+            tasks.push(executor.spawn(async move { braft.run().await }));
+        }
+
+        smol::Timer::after(Duration::from_millis(1500)).await;
+
+        for handle in &handles {
+            handle.shutdown();
+        }
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        let hashes: Vec<Vec<(u64, u64)>> =
+            hashes.iter().map(|h| h.lock().unwrap().clone()).collect();
+
+        // Voters locked on epoch of crashed proposer, proposer of next round proposes it again.
+        assert_eq!(hashes[0].first(), Some(&(1, 101)));
+        for h in &hashes[1..] {
+            assert_eq!(h.first(), Some(&(1, 101)));
+        }
+
+        // No epoch is committed with different hashes.
+        for a in &hashes {
+            for b in &hashes {
+                for (epoch_id, epoch_hash) in a {
+                    assert!(b.iter().all(|(e, h)| e != epoch_id || h == epoch_hash));
+                }
+            }
+        }
+    }));
+}
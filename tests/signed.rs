@@ -0,0 +1,283 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use cluster::{timer, voters, ClusterApp, ClusterConsensus, ClusterSigner, Hub};
+use consensus_rs::{
+    algorithm::BRaft,
+    codec::{round_message, vote_message, Codec},
+    network::SignedNetwork,
+    packet::Packet,
+    Network, Signer, Transport, VoteSign, VoterConfig, VoterSet,
+};
+use smol::{future, LocalExecutor};
+
+mod cluster;
+mod utils;
+
+/// Encode message in format of `SignedNetwork`.
+fn message(node_id: u8, key: u8, pkt: &Packet<u64, u64, Vec<u8>>) -> Vec<u8> {
+    let pkt = pkt.to_bytes();
+
+    let mut signed = b"consensus-rs/packet".to_vec();
+    vec![node_id].encode(&mut signed);
+    signed.extend_from_slice(&pkt);
+
+    let mut out = Vec::new();
+    vec![node_id].encode(&mut out);
+    pkt.encode(&mut out);
+    ClusterSigner(vec![key]).sign(&signed).encode(&mut out);
+    out
+}
+
+/// Signer counting verifications.
+struct CountSigner {
+    inner: ClusterSigner,
+    verified: Arc<AtomicUsize>,
+}
+
+impl Signer<ClusterConsensus> for CountSigner {
+    fn sign(&self, msg: &[u8]) -> Vec<u8> {
+        self.inner.sign(msg)
+    }
+
+    fn verify(&self, public_key: &Vec<u8>, msg: &[u8], sign: &Vec<u8>) -> bool {
+        self.verified.fetch_add(1, Ordering::Relaxed);
+        self.inner.verify(public_key, msg, sign)
+    }
+}
+
+#[test]
+fn three_node_cluster() {
+    utils::init();
+
+    let executor = LocalExecutor::new();
+
+    smol::block_on(executor.run(async {
+        let hub = Hub::default();
+
+        let mut handles = Vec::new();
+        let mut tasks = Vec::new();
+        let mut committed = Vec::new();
+
+        for i in 1..=3u8 {
+            let network = SignedNetwork::new(vec![i], hub.join(i), ClusterSigner(vec![i]));
+            let consensus = ClusterConsensus::new(3, Duration::from_millis(100), timer);
+            let app = ClusterApp::new(3);
+            committed.push(app.committed.clone());
+
+            let mut braft = BRaft::new(network, consensus, app).await.unwrap();
+            handles.push(braft.control_handle());
+            tasks.push(executor.spawn(async move { braft.run().await }));
+        }
+
+        smol::Timer::after(Duration::from_millis(1000)).await;
+
+        for handle in &handles {
+            handle.shutdown();
+        }
+
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        let proposer = committed[0].lock().unwrap().clone();
+        assert!(proposer.len() >= 3);
+
+        for c in &committed[1..] {
+            let c = c.lock().unwrap();
+            assert!(proposer.starts_with(&c[..c.len().min(proposer.len())]));
+            assert!(c.len() >= 2);
+        }
+    }));
+}
+
+#[test]
+fn sign_and_verify() {
+    utils::init();
+
+    smol::block_on(async {
+        let hub = Hub::default();
+        let voter_config = VoterConfig::Single(VoterSet::new(voters(3)).unwrap());
+
+        let mut node = SignedNetwork::new(vec![1], hub.join(1), ClusterSigner(vec![1]));
+        let mut peer = SignedNetwork::new(vec![2], hub.join(2), ClusterSigner(vec![2]));
+        let raw = hub.join(3);
+
+        node.update_voters(&voter_config);
+        peer.update_voters(&voter_config);
+
+        // Vote of peer is signed with index in voter set.
+        peer.send_unsigned(
+            Some(vec![1]),
+            Packet::response_propose_from_id_hash(1, 100, 0),
+        );
+
+        let (pkt, sender) = node.recv().await.unwrap();
+        assert_eq!(sender, vec![2]);
+        match pkt {
+            Packet::ResponsePropose(p) => {
+                let sign = p.vote_sign.unwrap();
+                assert_eq!(sign.idx, 1);
+                assert!(ClusterSigner(vec![1]).verify(
+                    &vec![2],
+                    &vote_message(&1u64, &100u64),
+                    &sign.sign
+                ));
+            }
+            _ => panic!("unexpected packet"),
+        }
+
+        let commit = |vote_signs| Packet::broadcast_commit_from_id_hash(2, 200, vote_signs);
+        let vote = |idx: u64, key: u8| VoteSign {
+            idx,
+            sign: ClusterSigner(vec![key]).sign(&vote_message(&2u64, &200u64)),
+        };
+
+        // Garbage.
+        raw.send(Some(vec![1]), vec![1, 2, 3]);
+        // Signed with key of other node.
+        raw.send(Some(vec![1]), message(3, 2, &commit(vec![])));
+        // Not a voter.
+        raw.send(Some(vec![1]), message(4, 4, &commit(vec![])));
+        // Bad vote signature.
+        raw.send(Some(vec![1]), message(3, 3, &commit(vec![vote(0, 2)])));
+        // Valid.
+        raw.send(
+            Some(vec![1]),
+            message(3, 3, &commit(vec![vote(0, 1), vote(2, 3)])),
+        );
+
+        // Vote signatures are verified on demand.
+        let (pkt, sender) = node.recv().await.unwrap();
+        assert_eq!(sender, vec![3]);
+        assert!(!node.verify_votes(&pkt));
+
+        let (pkt, sender) = node.recv().await.unwrap();
+        assert_eq!(sender, vec![3]);
+        assert!(node.verify_votes(&pkt));
+        match pkt {
+            Packet::BroadcastCommit(p) => assert_eq!(p.vote_signs.len(), 2),
+            _ => panic!("unexpected packet"),
+        }
+
+        let timeout = async {
+            smol::Timer::after(Duration::from_millis(100)).await;
+            None
+        };
+        let recv = async { Some(node.recv().await) };
+        assert!(future::or(recv, timeout).await.is_none());
+    });
+}
+
+#[test]
+fn sign_round_change() {
+    utils::init();
+
+    smol::block_on(async {
+        let hub = Hub::default();
+        let voter_config = VoterConfig::Single(VoterSet::new(voters(3)).unwrap());
+
+        let mut node = SignedNetwork::new(vec![1], hub.join(1), ClusterSigner(vec![1]));
+        let mut peer = SignedNetwork::new(vec![2], hub.join(2), ClusterSigner(vec![2]));
+        let raw = hub.join(3);
+
+        node.update_voters(&voter_config);
+        peer.update_voters(&voter_config);
+
+        peer.send_unsigned(
+            Some(vec![1]),
+            Packet::round_change_from_id_hash(1, 100, 4, None),
+        );
+
+        let (pkt, _) = node.recv().await.unwrap();
+        let sign = match pkt {
+            Packet::RoundChange(p) => p.vote_sign.unwrap(),
+            _ => panic!("unexpected packet"),
+        };
+        assert_eq!(sign.idx, 1);
+        assert!(ClusterSigner(vec![1]).verify(
+            &vec![2],
+            &round_message(&1u64, &100u64, 4),
+            &sign.sign
+        ));
+
+        let new_round =
+            |round, vote_signs| Packet::new_round_from_id_hash(1, 100, round, None, vote_signs);
+        let vote = |idx: u64, key: u8| VoteSign {
+            idx,
+            sign: ClusterSigner(vec![key]).sign(&round_message(&1u64, &100u64, 4)),
+        };
+
+        // Signatures of other round.
+        raw.send(
+            Some(vec![1]),
+            message(3, 3, &new_round(5, vec![vote(0, 1), vote(2, 3)])),
+        );
+        // Valid.
+        raw.send(
+            Some(vec![1]),
+            message(3, 3, &new_round(4, vec![vote(0, 1), vote(2, 3)])),
+        );
+
+        let (pkt, _) = node.recv().await.unwrap();
+        assert!(!node.verify_votes(&pkt));
+
+        let (pkt, _) = node.recv().await.unwrap();
+        assert!(node.verify_votes(&pkt));
+        match pkt {
+            Packet::NewRound(p) => assert_eq!(p.round, 4),
+            _ => panic!("unexpected packet"),
+        }
+    });
+}
+
+#[test]
+fn verify_votes_after_admission() {
+    utils::init();
+
+    smol::block_on(async {
+        let hub = Hub::default();
+        let verified = Arc::new(AtomicUsize::new(0));
+        let signer = CountSigner {
+            inner: ClusterSigner(vec![2]),
+            verified: verified.clone(),
+        };
+        let network = SignedNetwork::new(vec![2], hub.join(2), signer);
+        let raw = hub.join(3);
+
+        let consensus = ClusterConsensus::new(3, Duration::from_millis(100), timer);
+        let app = ClusterApp::new(3);
+        let mut braft = BRaft::new(network, consensus, app).await.unwrap();
+
+        // Round changes beyond round window, each with a valid vote signature.
+        for _ in 0..50 {
+            let mut rc = Packet::round_change_from_id_hash(0, 0, 40, None);
+            if let Packet::RoundChange(p) = &mut rc {
+                p.vote_sign = Some(VoteSign {
+                    idx: 2,
+                    sign: ClusterSigner(vec![3]).sign(&round_message(&0u64, &0u64, 40)),
+                });
+            }
+            raw.send(Some(vec![2]), message(3, 3, &rc));
+        }
+
+        let vote = |idx: u64, key: u8| VoteSign {
+            idx,
+            sign: ClusterSigner(vec![key]).sign(&vote_message(&1u64, &100u64)),
+        };
+        let commit =
+            Packet::broadcast_commit_from_id_hash(1, 100, vec![vote(0, 1), vote(1, 2), vote(2, 3)]);
+        raw.send(Some(vec![2]), message(1, 1, &commit));
+
+        braft.do_tick().await.unwrap();
+        assert_eq!(braft.status().latest_epoch_id, 1);
+
+        // Message signature of each packet, vote signatures only of admitted commit.
+        assert_eq!(verified.load(Ordering::Relaxed), 51 + 3);
+    });
+}
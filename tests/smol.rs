@@ -0,0 +1,80 @@
+#![cfg(feature = "smol")]
+
+use std::time::Duration;
+
+use cluster::{vote_signer, ClusterApp, ClusterConsensus};
+use consensus_rs::{
+    algorithm::BRaft,
+    packet::Packet,
+    runtime::smol::{spawn_local, timer, ChannelHub},
+    Network, VoteSign,
+};
+use smol::LocalExecutor;
+
+mod cluster;
+mod utils;
+
+#[test]
+fn three_node_cluster() {
+    utils::init();
+
+    let executor = LocalExecutor::new();
+
+    smol::block_on(executor.run(async {
+        let hub = ChannelHub::new();
+
+        let mut handles = Vec::new();
+        let mut tasks = Vec::new();
+        let mut committed = Vec::new();
+
+        for i in 1..=3u8 {
+            let network = hub.join(vec![i], vote_signer(i, 3));
+            let consensus = ClusterConsensus::new(3, Duration::from_millis(100), timer);
+            let app = ClusterApp::new(3);
+            committed.push(app.committed.clone());
+
+            let braft = BRaft::new(network, consensus, app).await.unwrap();
+            handles.push(braft.control_handle());
+            tasks.push(spawn_local(&executor, braft));
+        }
+
+        smol::Timer::after(Duration::from_millis(1000)).await;
+
+        for handle in &handles {
+            handle.shutdown();
+        }
+
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        let proposer = committed[0].lock().unwrap().clone();
+        assert!(proposer.len() >= 3);
+
+        for c in &committed[1..] {
+            let c = c.lock().unwrap();
+            assert!(proposer.starts_with(&c[..c.len().min(proposer.len())]));
+            assert!(c.len() >= 2);
+        }
+    }));
+}
+
+#[test]
+fn sign_round_of_round_change() {
+    let hub = ChannelHub::<ClusterConsensus>::new();
+
+    let network = hub.join(vec![1], |_, _, round| {
+        Some(VoteSign {
+            idx: 0,
+            sign: round.map(|r| r.to_le_bytes().to_vec()).unwrap_or_default(),
+        })
+    });
+
+    network.send_unsigned(None, Packet::round_change_from_id_hash(1, 100, 7, None));
+
+    let (pkt, _) = smol::block_on(network.recv()).unwrap();
+    match pkt {
+        Packet::RoundChange(p) => assert_eq!(p.vote_sign.unwrap().sign, 7u64.to_le_bytes()),
+        _ => panic!("unexpected packet"),
+    }
+}
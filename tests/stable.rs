@@ -0,0 +1,176 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use cluster::{timer, ClusterApp, ClusterConsensus, ClusterSigner, Hub};
+use consensus_rs::{
+    algorithm::BRaft,
+    event::EventKind,
+    handle::ControlHandle,
+    network::{GossipConfig, GossipTransport, SignedNetwork},
+    Role, Transport,
+};
+use smol::{LocalExecutor, Task};
+
+mod cluster;
+mod utils;
+
+type Events = Arc<Mutex<Vec<(u8, &'static str)>>>;
+
+struct Node {
+    handle: ControlHandle<ClusterConsensus>,
+    task: Task<consensus_rs::Result<()>>,
+    committed: Arc<Mutex<Vec<u64>>>,
+}
+
+/// Start three nodes with stable leader, log role and round changes.
+async fn start(executor: &LocalExecutor<'static>, events: &Events) -> Vec<Node> {
+    let hub = Hub::default();
+
+    start_on(executor, events, (1..=3u8).map(|i| hub.join(i))).await
+}
+
+/// Start three nodes with stable leader over transports of node 1, 2 and 3.
+async fn start_on<T: Transport<ClusterConsensus> + 'static>(
+    executor: &LocalExecutor<'static>,
+    events: &Events,
+    transports: impl IntoIterator<Item = T>,
+) -> Vec<Node> {
+    let mut nodes = Vec::new();
+
+    for (i, transport) in (1..=3u8).zip(transports) {
+        let network = SignedNetwork::new(vec![i], transport, ClusterSigner(vec![i]));
+        let mut consensus = ClusterConsensus::new(3, Duration::from_millis(100), timer);
+        consensus.stable_leader = true;
+        let app = ClusterApp::new(3);
+        let committed = app.committed.clone();
+
+        let mut braft = BRaft::new(network, consensus, app).await.unwrap();
+
+        let events = events.clone();
+        braft.subscribe(move |e| match &e.kind {
+            EventKind::RoleChanged { to, .. } if *to == Role::Proposer => {
+                events.lock().unwrap().push((i, "proposer"))
+            }
+            EventKind::RoundChanged { to, .. } if *to != 0 => {
+                events.lock().unwrap().push((i, "round"))
+            }
+            _ => {}
+        });
+
+        nodes.push(Node {
+            handle: braft.control_handle(),
+            task: executor.spawn(async move { braft.run().await }),
+            committed,
+        });
+    }
+
+    nodes
+}
+
+#[test]
+fn elect_on_leader_failure() {
+    utils::init();
+
+    let executor = LocalExecutor::new();
+    let events = Events::default();
+
+    smol::block_on(executor.run(async {
+        let mut nodes = start(&executor, &events).await;
+
+        smol::Timer::after(Duration::from_millis(300)).await;
+
+        // Leader fails.
+        let leader = nodes.remove(0);
+        leader.handle.shutdown();
+        leader.task.await.unwrap();
+        let before = nodes[1].committed.lock().unwrap().len();
+
+        smol::Timer::after(Duration::from_millis(1000)).await;
+
+        for node in &nodes {
+            node.handle.shutdown();
+        }
+        for node in nodes.iter_mut() {
+            (&mut node.task).await.unwrap();
+        }
+
+        // Next voter is elected, and keeps leading.
+        let events = events.lock().unwrap();
+        let elected: Vec<u8> = events
+            .iter()
+            .filter(|e| e.1 == "proposer")
+            .map(|e| e.0)
+            .collect();
+        assert_eq!(elected, vec![2]);
+
+        assert!(nodes[1].committed.lock().unwrap().len() >= before + 3);
+    }));
+}
+
+#[test]
+fn heartbeat_keeps_leader() {
+    utils::init();
+
+    let executor = LocalExecutor::new();
+    let events = Events::default();
+
+    smol::block_on(executor.run(async {
+        let nodes = start(&executor, &events).await;
+        nodes[0].handle.pause_proposing();
+
+        // Longer than budget of 32 packets on heartbeat interval of 50ms.
+        smol::Timer::after(Duration::from_millis(2500)).await;
+
+        // Followers may time out once leader stopped.
+        let before = events.lock().unwrap().clone();
+
+        for node in &nodes {
+            node.handle.shutdown();
+        }
+        for node in nodes {
+            node.task.await.unwrap();
+        }
+
+        // Idle leader isn't replaced.
+        let events = before;
+        assert!(!events.iter().any(|e| e.1 == "round"));
+        assert!(!events.iter().any(|e| e.1 == "proposer" && e.0 != 1));
+    }));
+}
+
+#[test]
+fn heartbeat_keeps_leader_over_gossip() {
+    utils::init();
+
+    let executor = LocalExecutor::new();
+    let events = Events::default();
+
+    smol::block_on(executor.run(async {
+        // Gossip drops frames already seen, each heartbeat must be a new frame.
+        let hub = Hub::default();
+        let transports = (1..=3u8).map(|i| {
+            let peers = (1..=3u8).filter(|p| *p != i).map(|p| vec![p]).collect();
+            GossipTransport::new(vec![i], hub.join(i), peers, GossipConfig::default())
+        });
+
+        let nodes = start_on(&executor, &events, transports).await;
+        nodes[0].handle.pause_proposing();
+
+        smol::Timer::after(Duration::from_millis(1000)).await;
+
+        let before = events.lock().unwrap().clone();
+
+        for node in &nodes {
+            node.handle.shutdown();
+        }
+        for node in nodes {
+            node.task.await.unwrap();
+        }
+
+        let events = before;
+        assert!(!events.iter().any(|e| e.1 == "round"));
+        assert!(!events.iter().any(|e| e.1 == "proposer" && e.0 != 1));
+    }));
+}
@@ -0,0 +1,271 @@
+#![cfg(feature = "tcp")]
+
+use std::{
+    io::{Read, Write},
+    net::SocketAddr,
+    time::Duration,
+};
+
+use cluster::{voters, ClusterApp, ClusterConsensus, ClusterSigner};
+use consensus_rs::{
+    algorithm::BRaft,
+    network::tcp::{TcpConfig, TcpNetwork, TcpTransport},
+    packet::Packet,
+    runtime::smol::{spawn_local, timer},
+    Network, Transport, VoteSign, VoterConfig, VoterSet,
+};
+use smol::{future, LocalExecutor};
+
+mod cluster;
+mod utils;
+
+fn addresses(n: u8) -> Vec<(Vec<u8>, SocketAddr)> {
+    (1..=n)
+        .map(|i| {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            (vec![i], listener.local_addr().unwrap())
+        })
+        .collect()
+}
+
+async fn bind(
+    node_id: u8,
+    key: u8,
+    addresses: &[(Vec<u8>, SocketAddr)],
+) -> TcpNetwork<ClusterConsensus, ClusterSigner> {
+    let config =
+        TcpConfig::default().with_reconnect(Duration::from_millis(10), Duration::from_millis(100));
+
+    TcpNetwork::bind(
+        vec![node_id],
+        addresses[node_id as usize - 1].1,
+        addresses.to_vec(),
+        ClusterSigner(vec![key]),
+        config,
+    )
+    .await
+    .unwrap()
+}
+
+#[test]
+fn three_node_cluster() {
+    utils::init();
+
+    let executor = LocalExecutor::new();
+
+    smol::block_on(executor.run(async {
+        let addresses = addresses(3);
+
+        let mut handles = Vec::new();
+        let mut tasks = Vec::new();
+        let mut committed = Vec::new();
+
+        for i in 1..=3u8 {
+            let network = bind(i, i, &addresses).await;
+            let consensus = ClusterConsensus::new(3, Duration::from_millis(100), timer);
+            let app = ClusterApp::new(3);
+            committed.push(app.committed.clone());
+
+            let braft = BRaft::new(network, consensus, app).await.unwrap();
+            handles.push(braft.control_handle());
+            tasks.push(spawn_local(&executor, braft));
+        }
+
+        smol::Timer::after(Duration::from_millis(1500)).await;
+
+        for handle in &handles {
+            handle.shutdown();
+        }
+
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        let proposer = committed[0].lock().unwrap().clone();
+        assert!(proposer.len() >= 3);
+
+        // Follower behind may skip epochs, but follows commits of proposer in order.
+        for c in &committed[1..] {
+            let c = c.lock().unwrap();
+            let mut epochs = proposer.iter();
+            assert!(c
+                .iter()
+                .filter(|e| proposer.last().is_some_and(|last| *e <= last))
+                .all(|e| epochs.any(|p| p == e)));
+            assert!(c.len() >= 2);
+        }
+    }));
+}
+
+#[test]
+fn reject_bad_signature() {
+    utils::init();
+
+    smol::block_on(async {
+        let addresses = addresses(3);
+        let voter_config = VoterConfig::Single(VoterSet::new(voters(3)).unwrap());
+
+        let mut node = bind(1, 1, &addresses).await;
+        // Claim to be node 2, but sign with key of node 3.
+        let mut impostor = bind(2, 3, &addresses).await;
+        let mut honest = bind(3, 3, &addresses).await;
+
+        node.update_voters(&voter_config);
+        impostor.update_voters(&voter_config);
+        honest.update_voters(&voter_config);
+
+        impostor.send_unsigned(
+            Some(vec![1]),
+            Packet::broadcast_commit_from_id_hash(2, 200, vec![]),
+        );
+        // Authenticated voter, but forged vote signatures.
+        let forged = (0..3).map(|idx| VoteSign {
+            idx,
+            sign: vec![idx as u8 + 1],
+        });
+        honest.send_unsigned(
+            Some(vec![1]),
+            Packet::broadcast_commit_from_id_hash(4, 400, forged.collect()),
+        );
+        honest.send_unsigned(
+            Some(vec![1]),
+            Packet::broadcast_commit_from_id_hash(3, 300, vec![]),
+        );
+
+        // Forged vote signatures are found on demand.
+        let (pkt, sender) = node.recv().await.unwrap();
+        assert_eq!(sender, vec![3]);
+        assert!(!node.verify_votes(&pkt));
+
+        let (pkt, sender) = node.recv().await.unwrap();
+        assert_eq!(sender, vec![3]);
+        assert!(node.verify_votes(&pkt));
+        match pkt {
+            Packet::BroadcastCommit(p) => assert_eq!(p.epoch_id, 3),
+            _ => panic!("unexpected packet"),
+        }
+
+        let timeout = async {
+            smol::Timer::after(Duration::from_millis(300)).await;
+            None
+        };
+        let recv = async { Some(node.recv().await) };
+        assert!(future::or(recv, timeout).await.is_none());
+    });
+}
+
+async fn transport(
+    node_id: u8,
+    key: u8,
+    addresses: &[(Vec<u8>, SocketAddr)],
+) -> TcpTransport<ClusterConsensus, ClusterSigner> {
+    let config = TcpConfig::default()
+        .with_reconnect(Duration::from_millis(10), Duration::from_millis(100))
+        .with_handshake_timeout(Duration::from_millis(100));
+
+    TcpTransport::bind(
+        vec![node_id],
+        addresses[node_id as usize - 1].1,
+        addresses.to_vec(),
+        ClusterSigner(vec![key]),
+        config,
+    )
+    .await
+    .unwrap()
+}
+
+/// Read until connection is closed by peer.
+fn closed(stream: &mut std::net::TcpStream) -> bool {
+    stream
+        .set_read_timeout(Some(Duration::from_secs(1)))
+        .unwrap();
+
+    let mut buf = [0u8; 64];
+    loop {
+        match stream.read(&mut buf) {
+            Ok(0) => return true,
+            Ok(_) => {}
+            Err(e) => return e.kind() == std::io::ErrorKind::ConnectionReset,
+        }
+    }
+}
+
+#[test]
+fn close_bad_handshake() {
+    utils::init();
+
+    smol::block_on(async {
+        let addresses = addresses(1);
+        let node = transport(1, 1, &addresses).await;
+        let addr = node.local_addr();
+
+        // Hello larger than limit.
+        let mut stream = std::net::TcpStream::connect(addr).unwrap();
+        stream.write_all(&(1u32 << 20).to_be_bytes()).unwrap();
+        assert!(smol::unblock(move || closed(&mut stream)).await);
+
+        // No hello in time.
+        let mut stream = std::net::TcpStream::connect(addr).unwrap();
+        assert!(smol::unblock(move || closed(&mut stream)).await);
+    });
+}
+
+#[test]
+fn hang_up_removed_voter() {
+    utils::init();
+
+    smol::block_on(async {
+        let addresses = addresses(3);
+        let all = VoterConfig::Single(VoterSet::new(voters(3)).unwrap());
+        let without_3 = VoterConfig::Single(VoterSet::new(voters(2)).unwrap());
+
+        let node = transport(1, 1, &addresses).await;
+        let peer = transport(3, 3, &addresses).await;
+
+        node.update_voters(&all);
+        peer.update_voters(&all);
+
+        peer.send(Some(vec![1]), vec![1]);
+        assert_eq!(node.recv().await.unwrap(), vec![1]);
+
+        node.update_voters(&without_3);
+        smol::Timer::after(Duration::from_millis(50)).await;
+
+        // Connection is closed, reconnect is rejected.
+        peer.send(Some(vec![1]), vec![2]);
+
+        let timeout = async {
+            smol::Timer::after(Duration::from_millis(300)).await;
+            None
+        };
+        let recv = async { Some(node.recv().await) };
+        assert!(future::or(recv, timeout).await.is_none());
+    });
+}
+
+#[test]
+fn reject_acceptor_with_wrong_key() {
+    utils::init();
+
+    smol::block_on(async {
+        let addresses = addresses(2);
+        let voter_config = VoterConfig::Single(VoterSet::new(voters(2)).unwrap());
+
+        // Listens on address of node 1, but can't sign as node 1.
+        let impostor = transport(1, 2, &addresses).await;
+        let dialer = transport(2, 2, &addresses).await;
+
+        impostor.update_voters(&voter_config);
+        dialer.update_voters(&voter_config);
+
+        // Dialer is authenticated by acceptor, but hangs up before sending anything.
+        dialer.send(Some(vec![1]), vec![1]);
+
+        let timeout = async {
+            smol::Timer::after(Duration::from_millis(300)).await;
+            None
+        };
+        let recv = async { Some(impostor.recv().await) };
+        assert!(future::or(recv, timeout).await.is_none());
+    });
+}
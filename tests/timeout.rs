@@ -0,0 +1,94 @@
+use std::time::Duration;
+
+use consensus_rs::{timeout::TimeoutPolicy, Role};
+
+#[test]
+fn default_policy() {
+    let policy = TimeoutPolicy::default();
+
+    assert_eq!(
+        policy.timeout(&Role::Follower, 0, 0, 7),
+        Duration::from_secs(1)
+    );
+    assert_eq!(
+        policy.timeout(&Role::Follower, 0, 10, 7),
+        Duration::from_secs(1)
+    );
+    assert_eq!(policy.quorum_grace(), Duration::ZERO);
+    assert_eq!(policy.heartbeat(), Duration::from_millis(500));
+}
+
+#[test]
+fn backoff_multiplier_and_cap() {
+    let policy = TimeoutPolicy::new(Duration::from_millis(100))
+        .with_step_multiplier(Role::Proposer, 1, 3.0)
+        .with_backoff(2.0)
+        .with_max(Duration::from_secs(1));
+
+    assert_eq!(
+        policy.timeout(&Role::Follower, 0, 0, 0),
+        Duration::from_millis(100)
+    );
+    assert_eq!(
+        policy.timeout(&Role::Follower, 0, 2, 0),
+        Duration::from_millis(400)
+    );
+    assert_eq!(
+        policy.timeout(&Role::Proposer, 1, 1, 0),
+        Duration::from_millis(600)
+    );
+    assert_eq!(
+        policy.timeout(&Role::Follower, 0, u64::MAX, 0),
+        Duration::from_secs(1)
+    );
+}
+
+#[test]
+fn jitter() {
+    let policy =
+        TimeoutPolicy::new(Duration::from_millis(100)).with_jitter(Duration::from_millis(10));
+
+    for entropy in [0, 1, 12345, u64::MAX] {
+        let timeout = policy.timeout(&Role::Follower, 0, 0, entropy);
+        assert!(timeout >= Duration::from_millis(100));
+        assert!(timeout <= Duration::from_millis(110));
+    }
+}
+
+#[test]
+fn saturate_on_overflow() {
+    let policy = TimeoutPolicy::new(Duration::MAX)
+        .with_step_multiplier(Role::Follower, 0, 1e300)
+        .with_backoff(1e300)
+        .with_max(Duration::MAX)
+        .with_jitter(Duration::MAX);
+
+    assert_eq!(
+        policy.timeout(&Role::Follower, 0, 5, u64::MAX),
+        Duration::MAX
+    );
+}
+
+#[test]
+#[should_panic(expected = "invalid backoff")]
+fn reject_nan_backoff() {
+    let _ = TimeoutPolicy::default().with_backoff(f64::NAN);
+}
+
+#[test]
+#[should_panic(expected = "invalid step multiplier")]
+fn reject_negative_multiplier() {
+    let _ = TimeoutPolicy::default().with_step_multiplier(Role::Follower, 0, -1.0);
+}
+
+#[test]
+#[should_panic(expected = "invalid step multiplier")]
+fn reject_zero_multiplier() {
+    let _ = TimeoutPolicy::default().with_step_multiplier(Role::Follower, 0, 0.0);
+}
+
+#[test]
+#[should_panic(expected = "invalid base timeout")]
+fn reject_zero_base() {
+    let _ = TimeoutPolicy::new(Duration::ZERO);
+}
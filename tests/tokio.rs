@@ -0,0 +1,125 @@
+#![cfg(feature = "tokio")]
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use cluster::{vote_signer, ClusterApp, ClusterConsensus};
+use consensus_rs::{
+    algorithm::BRaft,
+    event::EventKind,
+    runtime::tokio::{spawn, spawn_local, timer, ChannelHub},
+};
+
+mod cluster;
+mod utils;
+
+#[test]
+fn three_node_cluster() {
+    utils::init();
+
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .build()
+        .unwrap();
+
+    let local = tokio::task::LocalSet::new();
+
+    local.block_on(&rt, async {
+        let hub = ChannelHub::new();
+
+        let mut handles = Vec::new();
+        let mut committed = Vec::new();
+
+        for i in 1..=3u8 {
+            let network = hub.join(vec![i], vote_signer(i, 3));
+            let consensus = ClusterConsensus::new(3, Duration::from_millis(100), timer);
+            let app = ClusterApp::new(3);
+            committed.push(app.committed.clone());
+
+            let braft = BRaft::new(network, consensus, app).await.unwrap();
+            handles.push(braft.control_handle());
+            spawn_local(braft);
+        }
+
+        tokio::time::sleep(Duration::from_millis(1000)).await;
+
+        for handle in &handles {
+            handle.shutdown();
+        }
+
+        tokio::time::sleep(Duration::from_millis(300)).await;
+
+        let proposer = committed[0].lock().unwrap().clone();
+        assert!(proposer.len() >= 3);
+
+        for c in &committed[1..] {
+            let c = c.lock().unwrap();
+            assert!(proposer.starts_with(&c[..c.len().min(proposer.len())]));
+            assert!(c.len() >= 2);
+        }
+    });
+}
+
+#[test]
+fn multi_thread_cluster() {
+    utils::init();
+
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(2)
+        .enable_time()
+        .build()
+        .unwrap();
+
+    rt.block_on(async {
+        let hub = ChannelHub::new();
+
+        let mut handles = Vec::new();
+        let mut tasks = Vec::new();
+        let mut committed = Vec::new();
+        let events = Arc::new(Mutex::new(0));
+
+        for i in 1..=3u8 {
+            let network = hub.join(vec![i], vote_signer(i, 3));
+            let consensus = ClusterConsensus::new(3, Duration::from_millis(100), timer);
+            let app = ClusterApp::new(3);
+            committed.push(app.committed.clone());
+
+            let mut braft = BRaft::new(network, consensus, app).await.unwrap();
+
+            // Listener runs on worker thread of engine.
+            let events = events.clone();
+            braft.subscribe(move |e| {
+                if let EventKind::Committed { .. } = e.kind {
+                    *events.lock().unwrap() += 1;
+                }
+            });
+
+            handles.push(braft.control_handle());
+            tasks.push(spawn(braft));
+        }
+
+        tokio::time::sleep(Duration::from_millis(1000)).await;
+
+        for handle in &handles {
+            handle.shutdown();
+        }
+        for task in tasks {
+            task.await.unwrap().unwrap();
+        }
+
+        let proposer = committed[0].lock().unwrap().clone();
+        assert!(proposer.len() >= 3);
+
+        // Followers behind may catch up on a later commit, skipped epochs aren't committed.
+        for c in &committed[1..] {
+            let c = c.lock().unwrap();
+            assert!(c.windows(2).all(|w| w[0] < w[1]));
+            assert!(c.iter().all(|e| proposer.binary_search(e).is_ok()));
+        }
+
+        let total: usize = committed.iter().map(|c| c.lock().unwrap().len()).sum();
+        assert_eq!(*events.lock().unwrap(), total);
+    });
+}
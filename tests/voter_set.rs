@@ -0,0 +1,66 @@
+use consensus_rs::{quorum::FaultTolerance, Error, Voter, VoterConfig, VoterSet};
+
+fn voter(id: u8, weight: u64) -> Voter<Vec<u8>, Vec<u8>, u64> {
+    Voter {
+        voter_id: vec![id],
+        public_key: vec![id],
+        weight,
+    }
+}
+
+#[test]
+fn valid_voter_set() {
+    let voter_set = VoterSet::new(vec![voter(1, 1), voter(2, 3)]).unwrap();
+
+    assert_eq!(*voter_set.total_weight(), 4);
+    assert_eq!(voter_set.index_of(&vec![2]), Some(1));
+    assert!(!voter_set.contains(&vec![3]));
+}
+
+#[test]
+fn invalid_voter_set() {
+    assert!(matches!(
+        VoterSet::<Vec<u8>, Vec<u8>, u64>::new(vec![]),
+        Err(Error::EmptyVoterSet)
+    ));
+
+    assert!(matches!(
+        VoterSet::new(vec![voter(1, 1), voter(1, 2)]),
+        Err(Error::DuplicateVoter(1))
+    ));
+
+    assert!(matches!(
+        VoterSet::new(vec![voter(1, 1), voter(2, 0)]),
+        Err(Error::ZeroWeightVoter(1))
+    ));
+}
+
+#[test]
+fn joint_voter_config() {
+    let old = VoterSet::new(vec![voter(1, 1), voter(2, 1), voter(3, 1)]).unwrap();
+    let new = VoterSet::new(vec![voter(3, 1), voter(4, 1), voter(5, 1)]).unwrap();
+
+    let config = VoterConfig::Single(old.clone()).next(new.clone(), true);
+    assert!(config.is_joint());
+
+    assert_eq!(config.index_of(&vec![3]), Some(2));
+    assert_eq!(config.index_of(&vec![5]), Some(4));
+    assert_eq!(config.get(3).unwrap().voter_id, vec![4]);
+
+    let ids: Vec<_> = config.voters().iter().map(|v| v.voter_id[0]).collect();
+    assert_eq!(ids, vec![1, 2, 3, 4, 5]);
+
+    // Majority of old only.
+    assert!(!config.has_quorum(&[0, 1], FaultTolerance::Cft));
+    // Majority of new only.
+    assert!(!config.has_quorum(&[2, 3, 4], FaultTolerance::Cft));
+    // Majority of both.
+    assert!(config.has_quorum(&[1, 2, 3], FaultTolerance::Cft));
+
+    let config = config.next(new.clone(), true);
+    assert!(!config.is_joint());
+    assert!(config.has_quorum(&[0, 1], FaultTolerance::Cft));
+
+    assert!(!VoterConfig::Single(old.clone()).next(new, false).is_joint());
+    assert!(!VoterConfig::Single(old.clone()).next(old, true).is_joint());
+}